@@ -0,0 +1,632 @@
+use serde::Deserialize;
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use crate::client::OkdClient;
+use crate::protocol::EventItem;
+use crate::Result;
+
+// A timestamped sign that somebody is using a project.
+#[derive(Debug, Clone)]
+pub struct Evidence {
+    pub cause: String,
+    pub timestamp: DateTime<FixedOffset>,
+}
+
+impl Evidence {
+    fn new(cause: &str, timestamp: &str) -> Result<Evidence> {
+        Ok(Evidence {
+            cause: cause.to_string(),
+            timestamp: DateTime::parse_from_rfc3339(timestamp)?,
+        })
+    }
+}
+
+// Something ShelfLife can ask about a namespace's activity. To add a new
+// signal, implement this and add it to `Registry::from_config`.
+pub trait ActivitySource {
+    // What this source is called in the config file.
+    fn name(&self) -> &str;
+    // Everything this source can find out about a namespace.
+    fn collect(&self, client: &OkdClient, namespace: &str) -> Result<Vec<Evidence>>;
+}
+
+// ------------------------------
+// Config for activity sources
+#[derive(Debug, Clone, Deserialize)]
+pub struct SourceConfig {
+    pub name: String,
+    // How much to trust this source. A signal with weight 0.5 counts as if it
+    // were twice as old as it is.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Signals older than this don't count at all, e.g. "builds only count if
+    // they're less than 30 days old". They're still recorded.
+    pub max_age_days: Option<i64>,
+}
+
+fn default_weight() -> f64 { 1.0 }
+fn default_enabled() -> bool { true }
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ActivityConfig {
+    pub sources: Vec<SourceConfig>,
+    // HAProxy router access logs, as a file or a directory of them. Needed by
+    // the router source.
+    pub router_logs: Option<String>,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        let sources = vec!["deploymentconfigs", "builds", "deployments", "statefulsets", "cronjobs", "pods", "imagestreams", "events"];
+        ActivityConfig {
+            sources: sources
+                .into_iter()
+                .map(|name| SourceConfig { name: name.to_string(), weight: 1.0, enabled: true, max_age_days: None })
+                .collect(),
+            router_logs: None,
+        }
+    }
+}
+// ------------------------------
+
+impl ActivityConfig {
+    // Works out which of a project's stored signals it's been alive since, going
+    // by the config as it is now, not as it was at the last sync.
+    pub fn last_active(&self, signals: &HashMap<String, String>, now: DateTime<Utc>) -> Option<(String, DateTime<FixedOffset>)> {
+        let sources: Vec<&SourceConfig> = self.sources.iter().collect();
+        let signals = signals
+            .iter()
+            .filter_map(|(name, found)| DateTime::parse_from_rfc2822(found).ok().map(|found| (name.as_str(), found)));
+        weighted_latest(&sources, signals, now).map(|(name, found)| (name.to_string(), found))
+    }
+}
+
+// Picks the signal that counts as the most recent once they're all weighted.
+// Signals that aren't from a source (creation, heartbeat, revived) always
+// count, with weight 1. The timestamp that comes back is weighted: a build from
+// 10 days ago with weight 0.5 comes back as 20 days ago. Syncs and culls both
+// go through here, so they always agree on a winner. Ties go to the first.
+fn weighted_latest<'a>(
+    sources: &[&SourceConfig],
+    signals: impl Iterator<Item = (&'a str, DateTime<FixedOffset>)>,
+    now: DateTime<Utc>,
+) -> Option<(&'a str, DateTime<FixedOffset>)> {
+    let mut latest: Option<(&str, DateTime<FixedOffset>)> = None;
+    for (name, found) in signals {
+        let weight = match sources.iter().find(|source| source.name == name) {
+            Some(source_config) => {
+                if !source_config.enabled || source_config.weight <= 0.0 {
+                    continue;
+                }
+                if let Some(days) = source_config.max_age_days {
+                    if now.signed_duration_since(found) > Duration::days(days) {
+                        continue;
+                    }
+                }
+                source_config.weight
+            },
+            None => 1.0,
+        };
+        // Push it back by however much older it counts as. Capped at a few
+        // thousand years, so a tiny weight can't overflow.
+        let extra = now.signed_duration_since(found).num_seconds() as f64 * (1.0 / weight - 1.0);
+        let found = found - Duration::seconds(extra.min(1e11) as i64);
+        match latest {
+            Some((_, newest)) if newest >= found => (),
+            _ => latest = Some((name, found)),
+        }
+    }
+    latest
+}
+
+// The activity sources turned on in the config, and how much each one counts.
+// The default has none at all, for commands that never sync.
+#[derive(Default)]
+pub struct Registry {
+    sources: Vec<(Box<dyn ActivitySource>, SourceConfig)>,
+}
+
+impl Registry {
+    pub fn from_config(config: &ActivityConfig) -> Registry {
+        let mut sources: Vec<(Box<dyn ActivitySource>, SourceConfig)> = Vec::new();
+        for source_config in config.sources.iter().filter(|source| source.enabled) {
+            let source: Box<dyn ActivitySource> = match source_config.name.as_str() {
+                "deploymentconfigs" => Box::new(DeploymentConfigs),
+                "builds" => Box::new(Builds),
+                "deployments" => Box::new(AppsDeployments),
+                "statefulsets" => Box::new(StatefulSets),
+                "cronjobs" => Box::new(CronJobs),
+                "pods" => Box::new(Pods),
+                "imagestreams" => Box::new(ImageStreams),
+                "events" => Box::new(Events),
+                "router" => match &config.router_logs {
+                    Some(path) => match RouterLogs::load(path) {
+                        Ok(router_logs) => Box::new(router_logs),
+                        Err(e) => {
+                            println!("Could not read router logs at {}: {}. Skipping them.", path, e);
+                            warn!("Could not read router logs at {}: {}. Skipping them.", path, e);
+                            continue;
+                        },
+                    },
+                    None => {
+                        println!("The router source needs router_logs set. Skipping it.");
+                        warn!("The router source needs router_logs set. Skipping it.");
+                        continue;
+                    },
+                },
+                unknown => {
+                    println!("Unknown activity source: {}. Skipping it.", unknown);
+                    warn!("Unknown activity source: {}. Skipping it.", unknown);
+                    continue;
+                },
+            };
+            sources.push((source, source_config.clone()));
+        }
+        Registry { sources }
+    }
+
+    // Asks every source about a namespace, and keeps the most recent thing
+    // each one found, keyed by source name. A source that fails just doesn't
+    // get a say this time.
+    pub fn collect(&self, client: &OkdClient, namespace: &str) -> HashMap<String, Evidence> {
+        let mut signals = HashMap::new();
+        for (source, source_config) in self.sources.iter() {
+            if source_config.weight <= 0.0 {
+                continue;
+            }
+            let found = match source.collect(client, namespace) {
+                Ok(found) => found,
+                Err(e) => {
+                    println!("Activity source {} failed for {}: {}. Skipping it.", source.name(), namespace, e);
+                    warn!("Activity source {} failed for {}: {}. Skipping it.", source.name(), namespace, e);
+                    continue;
+                },
+            };
+            if let Some(newest) = found.into_iter().max_by_key(|evidence| evidence.timestamp) {
+                signals.insert(source.name().to_string(), newest);
+            }
+        }
+        signals
+    }
+
+    // Picks the signal that counts as the most recent, or the baseline if none
+    // of them beat it. The winner's timestamp and cause are what a project's
+    // age goes off of.
+    pub fn latest(&self, signals: &HashMap<String, Evidence>, baseline: Evidence) -> Evidence {
+        let sources: Vec<&SourceConfig> = self.sources.iter().map(|(_, source_config)| source_config).collect();
+        let found = signals.iter().map(|(name, evidence)| (name.as_str(), evidence.timestamp));
+        // The baseline goes first, so it wins ties. It isn't a source, so it has weight 1.
+        let candidates = std::iter::once(("", baseline.timestamp)).chain(found);
+        match weighted_latest(&sources, candidates, Utc::now()) {
+            Some((name, _)) if name != "" => signals[name].clone(),
+            _ => baseline,
+        }
+    }
+}
+
+/*                                    ACTIVITY SOURCES  */
+/*  --------------------------------------------------  */
+
+// Completed builds.
+pub struct Builds;
+
+impl ActivitySource for Builds {
+    fn name(&self) -> &str { "builds" }
+
+    fn collect(&self, client: &OkdClient, namespace: &str) -> Result<Vec<Evidence>> {
+        let builds_json = client.builds(namespace)?;
+        let mut builds = Vec::new();
+        for item in builds_json.items {
+            if let Some(x) = &item.status.completion_timestamp {
+                builds.push(Evidence::new("Build", x)?);
+            } else {
+                println!("Error fetching build timestamp.");
+            }
+        }
+        Ok(builds)
+    }
+}
+
+// DeploymentConfig condition updates.
+pub struct DeploymentConfigs;
+
+impl ActivitySource for DeploymentConfigs {
+    fn name(&self) -> &str { "deploymentconfigs" }
+
+    fn collect(&self, client: &OkdClient, namespace: &str) -> Result<Vec<Evidence>> {
+        let deploycfgs_json = client.deployment_configs(namespace)?;
+        let mut deploys = Vec::new();
+        for config in deploycfgs_json.items {
+            for condition in config.status.conditions {
+                deploys.push(Evidence::new("Deployment", &condition.last_update_time)?);
+            }
+        }
+        Ok(deploys)
+    }
+}
+
+// Plenty of people skip deploymentconfigs and use plain Kubernetes objects.
+// Not every cluster serves every one of these, so a failed call just means
+// one less thing to go off of.
+
+// Kubernetes deployment rollouts.
+pub struct AppsDeployments;
+
+impl ActivitySource for AppsDeployments {
+    fn name(&self) -> &str { "deployments" }
+
+    fn collect(&self, client: &OkdClient, namespace: &str) -> Result<Vec<Evidence>> {
+        let mut apps_deploys = Vec::new();
+        match client.deployments(namespace) {
+            Ok(apps_deploys_json) => {
+                for deployment in apps_deploys_json.items {
+                    for condition in deployment.status.and_then(|status| status.conditions).unwrap_or_default() {
+                        if let Some(x) = &condition.last_update_time {
+                            apps_deploys.push(Evidence::new("K8s Deployment", x)?);
+                        }
+                    }
+                }
+            },
+            Err(e) => warn!("Could not query deployments in {}: {}", namespace, e),
+        }
+        Ok(apps_deploys)
+    }
+}
+
+// StatefulSets don't keep rollout timestamps, so go off of their conditions,
+// or when they were created.
+pub struct StatefulSets;
+
+impl ActivitySource for StatefulSets {
+    fn name(&self) -> &str { "statefulsets" }
+
+    fn collect(&self, client: &OkdClient, namespace: &str) -> Result<Vec<Evidence>> {
+        let mut statefulsets = Vec::new();
+        match client.stateful_sets(namespace) {
+            Ok(statefulsets_json) => {
+                for statefulset in statefulsets_json.items {
+                    statefulsets.push(Evidence::new("StatefulSet", &statefulset.metadata.creation_timestamp)?);
+                    for condition in statefulset.status.and_then(|status| status.conditions).unwrap_or_default() {
+                        if let Some(x) = &condition.last_transition_time {
+                            statefulsets.push(Evidence::new("StatefulSet", x)?);
+                        }
+                    }
+                }
+            },
+            Err(e) => warn!("Could not query statefulsets in {}: {}", namespace, e),
+        }
+        Ok(statefulsets)
+    }
+}
+
+// The last time a cronjob ran.
+pub struct CronJobs;
+
+impl ActivitySource for CronJobs {
+    fn name(&self) -> &str { "cronjobs" }
+
+    fn collect(&self, client: &OkdClient, namespace: &str) -> Result<Vec<Evidence>> {
+        let mut cronjobs = Vec::new();
+        match client.cron_jobs(namespace) {
+            Ok(cronjobs_json) => {
+                for cronjob in cronjobs_json.items {
+                    if let Some(status) = cronjob.status {
+                        for x in [status.last_schedule_time, status.last_successful_time].iter().flatten() {
+                            cronjobs.push(Evidence::new("CronJob", x)?);
+                        }
+                    }
+                }
+            },
+            Err(e) => warn!("Could not query cronjobs in {}: {}", namespace, e),
+        }
+        Ok(cronjobs)
+    }
+}
+
+// When pods were created, and when their containers last started. Catches
+// manual rollouts and restarts that never show up as a build or deployment.
+// Containers the kubelet has restarted don't count, or a crash loop would keep
+// a dead project looking busy forever.
+pub struct Pods;
+
+impl ActivitySource for Pods {
+    fn name(&self) -> &str { "pods" }
+
+    fn collect(&self, client: &OkdClient, namespace: &str) -> Result<Vec<Evidence>> {
+        let mut pods = Vec::new();
+        match client.pods(namespace) {
+            Ok(pods_json) => {
+                for pod in pods_json.items {
+                    pods.push(Evidence::new("Pod", &pod.metadata.creation_timestamp)?);
+                    let containers = pod.status.and_then(|status| status.container_statuses).unwrap_or_default();
+                    for container in containers {
+                        if container.restart_count > 0 {
+                            continue;
+                        }
+                        let started_at = container.state
+                            .and_then(|state| state.running)
+                            .and_then(|running| running.started_at);
+                        if let Some(x) = &started_at {
+                            pods.push(Evidence::new("Pod", x)?);
+                        }
+                    }
+                }
+            },
+            Err(e) => warn!("Could not query pods in {}: {}", namespace, e),
+        }
+        Ok(pods)
+    }
+}
+
+// Images pushed straight to the internal registry. Every tag keeps a history of
+// the images it's pointed at, and when.
+pub struct ImageStreams;
+
+impl ActivitySource for ImageStreams {
+    fn name(&self) -> &str { "imagestreams" }
+
+    fn collect(&self, client: &OkdClient, namespace: &str) -> Result<Vec<Evidence>> {
+        let mut pushes = Vec::new();
+        match client.image_streams(namespace) {
+            Ok(imagestreams_json) => {
+                for imagestream in imagestreams_json.items {
+                    let tags = imagestream.status.and_then(|status| status.tags).unwrap_or_default();
+                    for tag in tags {
+                        for x in tag.items.unwrap_or_default() {
+                            pushes.push(Evidence::new("ImagePush", &x.created)?);
+                        }
+                    }
+                }
+            },
+            Err(e) => warn!("Could not query imagestreams in {}: {}", namespace, e),
+        }
+        Ok(pushes)
+    }
+}
+
+// The newest Normal event in the namespace. Events only stick around for a few
+// hours, so this mostly catches things other sources can't see. Warnings are
+// left out, since a crash-looping pod makes those forever.
+pub struct Events;
+
+// Our own spin-downs make these. They shouldn't count as somebody using the project.
+const TEARDOWN_REASONS: [&str; 2] = ["Killing", "SuccessfulDelete"];
+
+// Scaling a deploymentconfig makes one of these, e.g. "Scaled replication
+// controller "myapp-3" from 1 to 0". Scaling up is somebody using the project,
+// but scaling down to nothing is what our spin-downs do.
+fn is_teardown(event: &EventItem) -> bool {
+    match event.reason.as_deref() {
+        Some(reason) if TEARDOWN_REASONS.contains(&reason) => true,
+        Some("ReplicationControllerScaled") => event.message.as_deref().is_some_and(|message| message.ends_with(" to 0")),
+        _ => false,
+    }
+}
+
+impl ActivitySource for Events {
+    fn name(&self) -> &str { "events" }
+
+    fn collect(&self, client: &OkdClient, namespace: &str) -> Result<Vec<Evidence>> {
+        let mut events = Vec::new();
+        match client.events(namespace) {
+            Ok(events_json) => {
+                for event in events_json.items {
+                    if event.type_.as_deref() != Some("Normal") {
+                        continue;
+                    }
+                    if is_teardown(&event) {
+                        continue;
+                    }
+                    // Newer events use eventTime, older ones lastTimestamp.
+                    let x = event.event_time
+                        .or(event.last_timestamp)
+                        .unwrap_or(event.metadata.creation_timestamp);
+                    events.push(Evidence::new("Event", &x)?);
+                }
+            },
+            Err(e) => warn!("Could not query events in {}: {}", namespace, e),
+        }
+        Ok(events)
+    }
+}
+
+// The last time anybody sent a request to one of a project's routes, according
+// to the HAProxy router's access logs. The logs are read once, up front.
+//
+// The router has to capture the Host header, so each line looks something like:
+// ... [10/Mar/2020:12:00:00.123] public be_http:myproject:myroute/pod ... {myapp.example.com} "GET / HTTP/1.1"
+pub struct RouterLogs {
+    last_request: HashMap<String, DateTime<FixedOffset>>, // Hostname -> latest request
+}
+
+lazy_static! {
+    static ref ACCEPT_DATE: Regex = Regex::new(r"\[(\d{2}/\w{3}/\d{4}:\d{2}:\d{2}:\d{2})(\.\d+)?\]").unwrap();
+    static ref CAPTURED_HOST: Regex = Regex::new(r"\{([^|}\s]+)").unwrap();
+}
+
+impl RouterLogs {
+    pub fn load(path: &str) -> Result<RouterLogs> {
+        let mut router_logs = RouterLogs { last_request: HashMap::new() };
+        let path = Path::new(path);
+        if path.is_dir() {
+            for entry in fs::read_dir(path)? {
+                let file = entry?.path();
+                // Rotated logs are usually compressed. We can't read those.
+                if file.is_file() && file.extension().map_or(true, |extension| extension != "gz") {
+                    // One bad file shouldn't cost us the rest of them.
+                    if let Err(e) = router_logs.read(&file) {
+                        println!("Could not read router log {}: {}. Skipping it.", file.display(), e);
+                        warn!("Could not read router log {}: {}. Skipping it.", file.display(), e);
+                    }
+                }
+            }
+        } else {
+            router_logs.read(path)?;
+        }
+        info!("Read router logs for {} hostnames.", router_logs.last_request.len());
+        Ok(router_logs)
+    }
+
+    fn read(&mut self, file: &Path) -> Result<()> {
+        let reader = BufReader::new(File::open(file)?);
+        for line in reader.lines() {
+            // A log that got rotated out from under us reads as an error.
+            let line = line?;
+            if let Some((host, date)) = parse_router_line(&line) {
+                let latest = self.last_request.entry(host).or_insert(date);
+                if date > *latest {
+                    *latest = date;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Pulls the hostname and the time a request was accepted out of a log line.
+// HAProxy logs in the router's local time, which we assume is ours too.
+fn parse_router_line(line: &str) -> Option<(String, DateTime<FixedOffset>)> {
+    let date = ACCEPT_DATE.captures(line)?.get(1)?.as_str();
+    let date = NaiveDateTime::parse_from_str(date, "%d/%b/%Y:%H:%M:%S").ok()?;
+    let date: DateTime<FixedOffset> = Local.from_local_datetime(&date).single()?.into();
+    let host = CAPTURED_HOST.captures(line)?.get(1)?.as_str();
+    let host = host.split(':').next()?.to_lowercase();
+    Some((host, date))
+}
+
+impl ActivitySource for RouterLogs {
+    fn name(&self) -> &str { "router" }
+
+    fn collect(&self, client: &OkdClient, namespace: &str) -> Result<Vec<Evidence>> {
+        let mut requests = Vec::new();
+        let routes_json = match client.routes(namespace) {
+            Ok(routes_json) => routes_json,
+            Err(e) => {
+                warn!("Could not query routes in {}: {}", namespace, e);
+                return Ok(requests);
+            },
+        };
+        for route in routes_json.items {
+            let host = match route.spec.host {
+                Some(host) => host.to_lowercase(),
+                None => continue,
+            };
+            if let Some(date) = self.last_request.get(&host) {
+                requests.push(Evidence {
+                    cause: "Traffic".to_string(),
+                    timestamp: *date,
+                });
+            }
+        }
+        Ok(requests)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../tests/fixtures/router.log");
+
+    fn days_ago(cause: &str, days: i64) -> Evidence {
+        Evidence { cause: cause.to_string(), timestamp: (Utc::now() - Duration::days(days)).into() }
+    }
+
+    #[test]
+    fn syncs_and_culls_weigh_signals_the_same() {
+        let builds = SourceConfig { name: "builds".to_string(), weight: 0.5, enabled: true, max_age_days: None };
+        let registry = Registry { sources: vec![(Box::new(Builds), builds.clone())] };
+        let config = ActivityConfig { sources: vec![builds], router_logs: None };
+        let mut found = HashMap::new();
+        found.insert("builds".to_string(), days_ago("Build", 10));
+
+        // A build from 10 days ago counts as 20, so it loses to a project made 15 days ago...
+        let creation = days_ago("Creation", 15);
+        assert_eq!(registry.latest(&found, creation.clone()).cause, "Creation");
+        let mut signals: HashMap<String, String> = found.iter()
+            .map(|(name, evidence)| (name.to_string(), evidence.timestamp.to_rfc2822()))
+            .collect();
+        signals.insert("creation".to_string(), creation.timestamp.to_rfc2822());
+        assert_eq!(config.last_active(&signals, Utc::now()).unwrap().0, "creation");
+
+        // ...and beats one made 25 days ago.
+        let creation = days_ago("Creation", 25);
+        assert_eq!(registry.latest(&found, creation.clone()).cause, "Build");
+        signals.insert("creation".to_string(), creation.timestamp.to_rfc2822());
+        assert_eq!(config.last_active(&signals, Utc::now()).unwrap().0, "builds");
+    }
+
+    fn sample_line(index: usize) -> &'static str {
+        SAMPLE.lines().nth(index).unwrap()
+    }
+
+    fn local(date: &str) -> DateTime<FixedOffset> {
+        let date = NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap();
+        Local.from_local_datetime(&date).single().unwrap().into()
+    }
+
+    #[test]
+    fn router_line_drops_the_port_and_folds_case() {
+        let (host, date) = parse_router_line(sample_line(0)).unwrap();
+        assert_eq!(host, "myapp.example.com");
+        assert_eq!(date, local("2020-03-11 08:30:00"));
+    }
+
+    #[test]
+    fn router_line_stops_at_the_next_capture() {
+        let (host, date) = parse_router_line(sample_line(3)).unwrap();
+        assert_eq!(host, "api.example.com");
+        assert_eq!(date, local("2020-03-12 09:15:00"));
+    }
+
+    #[test]
+    fn router_line_without_a_host_capture_is_skipped() {
+        assert!(parse_router_line(sample_line(2)).is_none());
+        assert!(parse_router_line("not a log line at all").is_none());
+    }
+
+    #[test]
+    fn router_logs_keep_the_newest_request() {
+        let mut router_logs = RouterLogs { last_request: HashMap::new() };
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/router.log");
+        router_logs.read(&path).unwrap();
+        // The older request comes later in the file, and mustn't win.
+        assert_eq!(router_logs.last_request["myapp.example.com"], local("2020-03-11 08:30:00"));
+        assert_eq!(router_logs.last_request.len(), 2);
+    }
+
+    fn event(reason: &str, message: &str) -> EventItem {
+        serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "myapp.1", "creationTimestamp": "2020-03-10T12:00:00Z" },
+            "type": "Normal",
+            "reason": reason,
+            "message": message,
+        })).unwrap()
+    }
+
+    #[test]
+    fn our_own_spin_downs_are_not_activity() {
+        assert!(is_teardown(&event("Killing", "Stopping container myapp")));
+        assert!(is_teardown(&event("SuccessfulDelete", "Deleted pod: myapp-3-abcde")));
+        assert!(is_teardown(&event("ReplicationControllerScaled", "Scaled replication controller \"myapp-3\" from 2 to 0")));
+        assert!(!is_teardown(&event("ReplicationControllerScaled", "Scaled replication controller \"myapp-3\" from 0 to 1")));
+        assert!(!is_teardown(&event("Started", "Started container myapp")));
+    }
+
+    #[test]
+    fn missing_router_logs_leave_the_router_source_out() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/no-such-router.log");
+        assert!(RouterLogs::load(path.to_str().unwrap()).is_err());
+
+        let router = SourceConfig { name: "router".to_string(), weight: 1.0, enabled: true, max_age_days: None };
+        let config = ActivityConfig { sources: vec![router], router_logs: Some(path.to_str().unwrap().to_string()) };
+        assert!(Registry::from_config(&config).sources.is_empty());
+    }
+}
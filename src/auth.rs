@@ -0,0 +1,357 @@
+use serde::Deserialize;
+use serde_json::json;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::PKey;
+use openssl::stack::Stack;
+use openssl::x509::X509;
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use crate::Result;
+
+// Where the kubelet mounts a pod's service account.
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+// ------------------------------
+// Config for talking to the OKD API over TLS
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    // Extra CAs to trust, on top of the system's and the kubeconfig's. PEM,
+    // as many certs as you like.
+    pub ca_bundle: Option<String>,
+    // Log in with a client certificate. Both or neither. These win over the
+    // kubeconfig's.
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    // Don't check the API server's certificate at all. Lab clusters only!
+    pub insecure_skip_verify: bool,
+}
+// ------------------------------
+
+// Who ShelfLife is and how it proves it, however we found out.
+pub struct Credentials {
+    pub base_url: String,
+    pub token: String,
+    // Service account tokens get rotated, so these get re-read on every call.
+    pub token_file: Option<PathBuf>,
+    pub tls: Tls,
+}
+
+// Everything the HTTP client needs to trust the cluster and be trusted back.
+// All PEM.
+#[derive(Default)]
+pub struct Tls {
+    pub ca: Option<Vec<u8>>,
+    pub client_cert: Option<Vec<u8>>,
+    pub client_key: Option<Vec<u8>>,
+    pub insecure: bool,
+}
+
+impl Tls {
+    // Layers the config file's TLS settings over whatever we logged in with.
+    pub fn apply(&mut self, config: &TlsConfig) -> Result<()> {
+        if let Some(path) = &config.ca_bundle {
+            let mut ca = self.ca.take().unwrap_or_default();
+            ca.push(b'\n');
+            ca.extend(fs::read(path)?);
+            self.ca = Some(ca);
+        }
+        match (&config.client_cert, &config.client_key) {
+            (Some(cert), Some(key)) => {
+                self.client_cert = Some(fs::read(cert)?);
+                self.client_key = Some(fs::read(key)?);
+            },
+            (None, None) => (),
+            _ => return Err(From::from("Error: tls needs both client_cert and client_key, not just one.")),
+        }
+        self.insecure |= config.insecure_skip_verify;
+        Ok(())
+    }
+
+    // Builds an HTTP client with these settings. Leave out the identity when
+    // acting for somebody else, or the cluster will think they're us.
+    pub fn http_client(&self, with_identity: bool) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().danger_accept_invalid_certs(self.insecure);
+        if let Some(ca) = &self.ca {
+            // A bundle can hold more than one cert, and reqwest only reads the first.
+            for cert in X509::stack_from_pem(ca)? {
+                builder = builder.add_root_certificate(reqwest::Certificate::from_der(&cert.to_der()?)?);
+            }
+        }
+        if with_identity {
+            match (&self.client_cert, &self.client_key) {
+                (Some(cert), Some(key)) => builder = builder.identity(identity(cert, key)?),
+                (None, None) => (),
+                _ => return Err(From::from("Error: Need both a client certificate and a key, not just one.")),
+            }
+        }
+        Ok(builder.build()?)
+    }
+}
+
+// reqwest only takes PKCS#12 identities, but kubeconfigs hand out PEM.
+fn identity(cert: &[u8], key: &[u8]) -> Result<reqwest::Identity> {
+    let mut certs = X509::stack_from_pem(cert)?.into_iter();
+    let leaf = certs.next().ok_or("Error: The client certificate file has no certificates in it.")?;
+    let mut chain = Stack::new()?;
+    for cert in certs {
+        chain.push(cert)?;
+    }
+    let key = PKey::private_key_from_pem(key)?;
+    let mut builder = Pkcs12::builder();
+    builder.name("shelflife").pkey(&key).cert(&leaf).ca(chain);
+    let pkcs12 = builder.build2("")?;
+    Ok(reqwest::Identity::from_pkcs12_der(&pkcs12.to_der()?, "")?)
+}
+
+// Picks a way to log in, in this order:
+//   1. OKD_TOKEN and ENDPOINT, like always
+//   2. The kubeconfig in KUBECONFIG, using KUBE_CONTEXT if it's set
+//   3. The service account of the pod we're running in
+pub fn from_env() -> Result<Credentials> {
+    let token = env::var("OKD_TOKEN").unwrap_or_default();
+    if token != "" {
+        let endpoint = env::var("ENDPOINT")?;
+        return Ok(from_token(&format!("https://{}", endpoint), &token));
+    }
+    match env::var("KUBECONFIG") {
+        Ok(path) if path != "" => {
+            let context = env::var("KUBE_CONTEXT").ok().filter(|c| c != "");
+            info!("Logging in with the kubeconfig at {}", &path);
+            return from_kubeconfig(Path::new(&path), context.as_deref());
+        },
+        _ => (),
+    }
+    if Path::new(SERVICE_ACCOUNT_DIR).join("token").exists() {
+        info!("Logging in as this pod's service account.");
+        return in_cluster();
+    }
+    Err(From::from(
+        "Error: No way to log in to OKD. Set OKD_TOKEN and ENDPOINT, or KUBECONFIG, or run ShelfLife in a pod.",
+    ))
+}
+
+pub fn from_token(base_url: &str, token: &str) -> Credentials {
+    Credentials {
+        base_url: base_url.to_string(),
+        token: token.to_string(),
+        token_file: None,
+        tls: Tls::default(),
+    }
+}
+
+// Runs as the pod's service account, against the cluster it lives in.
+pub fn in_cluster() -> Result<Credentials> {
+    let host = env::var("KUBERNETES_SERVICE_HOST")?;
+    let port = env::var("KUBERNETES_SERVICE_PORT")?;
+    let dir = Path::new(SERVICE_ACCOUNT_DIR);
+    let token_file = dir.join("token");
+    Ok(Credentials {
+        base_url: service_url(&host, &port),
+        token: fs::read_to_string(&token_file)?.trim().to_string(),
+        token_file: Some(token_file),
+        tls: Tls {
+            ca: Some(fs::read(dir.join("ca.crt"))?),
+            ..Tls::default()
+        },
+    })
+}
+
+// The API's URL from the address Kubernetes hands pods. IPv6 hosts need brackets.
+fn service_url(host: &str, port: &str) -> String {
+    if host.contains(':') {
+        format!("https://[{}]:{}", host, port)
+    } else {
+        format!("https://{}:{}", host, port)
+    }
+}
+
+/*                                        KUBECONFIG  */
+/*  --------------------------------------------------  */
+
+// Writes credentials back out as a kubeconfig with a single context.
+pub fn kubeconfig(base_url: &str, token: &str, tls: &Tls) -> Result<String> {
+    let mut cluster = json!({ "server": base_url });
+    if tls.insecure {
+        // oc won't take a CA along with this, and wouldn't check it anyway.
+        cluster["insecure-skip-tls-verify"] = json!(true);
+    } else if let Some(ca) = &tls.ca {
+        cluster["certificate-authority-data"] = json!(base64::encode(ca));
+    }
+    let mut user = json!({});
+    if token != "" {
+        user["token"] = json!(token);
+    }
+    if let (Some(cert), Some(key)) = (&tls.client_cert, &tls.client_key) {
+        user["client-certificate-data"] = json!(base64::encode(cert));
+        user["client-key-data"] = json!(base64::encode(key));
+    }
+    let kubeconfig = json!({
+        "apiVersion": "v1",
+        "kind": "Config",
+        "current-context": "shelflife",
+        "clusters": [{ "name": "shelflife", "cluster": cluster }],
+        "users": [{ "name": "shelflife", "user": user }],
+        "contexts": [{ "name": "shelflife", "context": { "cluster": "shelflife", "user": "shelflife" } }],
+    });
+    Ok(serde_yaml::to_string(&kubeconfig)?)
+}
+
+#[derive(Deserialize)]
+struct Kubeconfig {
+    #[serde(rename = "current-context", default)]
+    current_context: String,
+    #[serde(default)]
+    clusters: Vec<NamedCluster>,
+    #[serde(default)]
+    contexts: Vec<NamedContext>,
+    #[serde(default)]
+    users: Vec<NamedUser>,
+}
+
+#[derive(Deserialize)]
+struct NamedCluster {
+    name: String,
+    cluster: KubeCluster,
+}
+
+#[derive(Deserialize)]
+struct KubeCluster {
+    server: String,
+    #[serde(rename = "certificate-authority")]
+    certificate_authority: Option<String>,
+    #[serde(rename = "certificate-authority-data")]
+    certificate_authority_data: Option<String>,
+    #[serde(rename = "insecure-skip-tls-verify", default)]
+    insecure_skip_tls_verify: bool,
+}
+
+#[derive(Deserialize)]
+struct NamedContext {
+    name: String,
+    context: KubeContext,
+}
+
+#[derive(Deserialize)]
+struct KubeContext {
+    cluster: String,
+    user: String,
+}
+
+#[derive(Deserialize)]
+struct NamedUser {
+    name: String,
+    user: KubeUser,
+}
+
+#[derive(Deserialize)]
+struct KubeUser {
+    token: Option<String>,
+    #[serde(rename = "tokenFile")]
+    token_file: Option<String>,
+    #[serde(rename = "client-certificate")]
+    client_certificate: Option<String>,
+    #[serde(rename = "client-certificate-data")]
+    client_certificate_data: Option<String>,
+    #[serde(rename = "client-key")]
+    client_key: Option<String>,
+    #[serde(rename = "client-key-data")]
+    client_key_data: Option<String>,
+}
+
+// Reads the cluster and user for a context out of a kubeconfig, or for
+// current-context if there isn't one. Only one file, no KUBECONFIG=a:b merging,
+// and no exec or auth-provider plugins.
+pub fn from_kubeconfig(path: &Path, context: Option<&str>) -> Result<Credentials> {
+    let kubeconfig: Kubeconfig = serde_yaml::from_reader(File::open(path)?)?;
+    let context_name = context.unwrap_or(&kubeconfig.current_context);
+    let context = &kubeconfig.contexts.iter()
+        .find(|c| c.name == context_name)
+        .ok_or(format!("Error: No context named '{}' in {}", context_name, path.display()))?
+        .context;
+    let cluster = &kubeconfig.clusters.iter()
+        .find(|c| c.name == context.cluster)
+        .ok_or(format!("Error: No cluster named '{}' in {}", context.cluster, path.display()))?
+        .cluster;
+    let user = &kubeconfig.users.iter()
+        .find(|u| u.name == context.user)
+        .ok_or(format!("Error: No user named '{}' in {}", context.user, path.display()))?
+        .user;
+
+    // Files in a kubeconfig are relative to the kubeconfig.
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let token_file = user.token_file.as_ref().map(|f| dir.join(f));
+    let token = match (&user.token, &token_file) {
+        (Some(token), _) => token.clone(),
+        (None, Some(file)) => fs::read_to_string(file)?.trim().to_string(),
+        (None, None) => String::new(),
+    };
+    Ok(Credentials {
+        base_url: cluster.server.trim_end_matches('/').to_string(),
+        token,
+        token_file: if user.token.is_some() { None } else { token_file },
+        tls: Tls {
+            ca: inline_or_file(dir, &cluster.certificate_authority_data, &cluster.certificate_authority)?,
+            client_cert: inline_or_file(dir, &user.client_certificate_data, &user.client_certificate)?,
+            client_key: inline_or_file(dir, &user.client_key_data, &user.client_key)?,
+            insecure: cluster.insecure_skip_tls_verify,
+        },
+    })
+}
+
+// Kubeconfigs can embed a PEM as base64, or point at a file holding it.
+fn inline_or_file(dir: &Path, data: &Option<String>, file: &Option<String>) -> Result<Option<Vec<u8>>> {
+    match (data, file) {
+        (Some(data), _) => Ok(Some(base64::decode(data.trim())?)),
+        (None, Some(file)) => Ok(Some(fs::read(dir.join(file))?)),
+        (None, None) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/kubeconfig/config")
+    }
+
+    #[test]
+    fn kubeconfig_takes_inline_certs_from_the_current_context() {
+        let credentials = from_kubeconfig(&fixture(), None).unwrap();
+        assert_eq!(credentials.base_url, "https://api.inline.example.com:6443");
+        assert_eq!(credentials.token, "inline-token");
+        assert!(credentials.token_file.is_none());
+        assert_eq!(credentials.tls.ca.unwrap(), b"inline ca\n");
+        assert_eq!(credentials.tls.client_cert.unwrap(), b"inline client cert\n");
+        assert_eq!(credentials.tls.client_key.unwrap(), b"inline client key\n");
+        assert!(!credentials.tls.insecure);
+    }
+
+    #[test]
+    fn kubeconfig_files_are_relative_to_the_kubeconfig() {
+        let credentials = from_kubeconfig(&fixture(), Some("files")).unwrap();
+        let dir = fixture().parent().unwrap().to_path_buf();
+        assert_eq!(credentials.base_url, "https://api.files.example.com:6443");
+        assert_eq!(credentials.token, "token-from-file");
+        assert_eq!(credentials.token_file, Some(dir.join("token")));
+        assert_eq!(credentials.tls.ca.unwrap(), b"fake ca\n");
+        assert_eq!(credentials.tls.client_cert.unwrap(), b"fake client cert\n");
+        assert_eq!(credentials.tls.client_key.unwrap(), b"fake client key\n");
+    }
+
+    #[test]
+    fn kubeconfig_without_the_context_cluster_or_user_is_an_error() {
+        let missing = |context| from_kubeconfig(&fixture(), Some(context)).err().unwrap().to_string();
+        assert!(missing("nope").contains("No context named 'nope'"));
+        assert!(missing("no-cluster").contains("No cluster named 'gone'"));
+        assert!(missing("no-user").contains("No user named 'gone'"));
+    }
+
+    #[test]
+    fn service_url_brackets_ipv6_hosts() {
+        assert_eq!(service_url("172.30.0.1", "443"), "https://172.30.0.1:443");
+        assert_eq!(service_url("fd02::1", "443"), "https://[fd02::1]:443");
+    }
+}
@@ -21,11 +21,20 @@ use log4rs::config::{Appender, Config, Root};
 
 use shelflife::{
                 check_env,
+                config::load_config,
+                activity::Registry,
+                client::OkdClient,
                 query_known_namespace,
                 check_expiry_dates,
-                get_call_api,
                 get_namespaces,
                 remove_db_item,
+                snooze_db_item,
+                set_db_item_ttl,
+                touch_db_item,
+                explain_namespace,
+                heartbeat,
+                set_db_item_ignore,
+                release_expired_ignores,
                 view_db,
                 Result
             };
@@ -50,9 +59,10 @@ fn main() -> Result<()> {
 
     log4rs::init_config(config)?;
 
-    let endpoint = env::var("ENDPOINT")?;
-    
-    let http_client = reqwest::Client::new();
+    // Load the lifecycle policy and friends.
+    let config = load_config()?;
+
+    let client = OkdClient::from_env(&config.tls)?.with_api_config(config.api.clone());
     let mongo_client = mongodb::Client::connect(
         &env::var("DB_ADDR")?,
         env::var("DB_PORT")?
@@ -117,16 +127,69 @@ fn main() -> Result<()> {
             .value_name("NAMESPACE")
             .help("Query API for project info about a namespace.")
             .takes_value(true))
+        .arg(Arg::with_name("explain")
+            .short("e")
+            .long("explain")
+            .value_name("NAMESPACE")
+            .help("Shows how a tracked namespace's age and next action are worked out, without doing anything.")
+            .takes_value(true))
         .arg(Arg::with_name("list")
             .short("l")
             .long("list")
             .help("Print namespaces currently tracked in the database."))
+        .arg(Arg::with_name("snooze")
+            .short("s")
+            .long("snooze")
+            .value_name("NAMESPACE")
+            .help("Leaves a tracked namespace alone until the date given by --until.")
+            .requires("until")
+            .takes_value(true))
+        .arg(Arg::with_name("ttl")
+            .long("ttl")
+            .value_name("NAMESPACE")
+            .help("Spins down and deletes a tracked namespace on the date given by --until, no matter how active it is.")
+            .requires("until")
+            .takes_value(true))
+        .arg(Arg::with_name("touch")
+            .short("t")
+            .long("touch")
+            .value_name("NAMESPACE")
+            .help("Records a heartbeat for a tracked namespace, resetting its clock. Use --by to say who.")
+            .takes_value(true))
+        .arg(Arg::with_name("serve")
+            .long("serve")
+            .value_name("ADDRESS")
+            .help("Listens for heartbeats over HTTP on ADDRESS (e.g. 0.0.0.0:8080), so projects can touch themselves. Plain HTTP, so put it behind a TLS route. Runs until killed.")
+            .takes_value(true))
+        .arg(Arg::with_name("until")
+            .long("until")
+            .value_name("DATE")
+            .help("The date (YYYY-MM-DD) an extension runs out. In ignore mode, when to stop ignoring the namespace.")
+            .takes_value(true))
+        .arg(Arg::with_name("reason")
+            .long("reason")
+            .value_name("REASON")
+            .help("Why the extension was granted, or why the namespace is being ignored.")
+            .takes_value(true))
+        .arg(Arg::with_name("by")
+            .long("by")
+            .value_name("NAME")
+            .help("Who granted the extension, touched the namespace, or asked for it to be ignored. Defaults to $USER.")
+            .takes_value(true))
         .arg(Arg::with_name("ignore_list")
             .short("i")
             .long("ignore_list")
             .help("Enables ignore mode for that command, performing operations on the list of ignored projects instead of the list of tracked projects."))
         .get_matches();
 
+    // Only syncing needs the activity sources, and reading router logs can
+    // take a while, so don't bother for anything else.
+    let syncing = matches.is_present("all") || matches.is_present("known") || matches.is_present("file");
+    let sources = match syncing {
+        true => Registry::from_config(&config.activity),
+        false => Registry::default(),
+    };
+
     let mut collection = "track";
     if matches.occurrences_of("ignore_list") > 0 {
         collection = "ignore";
@@ -135,11 +198,34 @@ fn main() -> Result<()> {
         info!("Running in tracking mode.")
     }
 
+    // Who's asking, and why. Used for extensions and ignore entries.
+    let user = env::var("USER").unwrap_or("unknown".to_string());
+    let until = matches.value_of("until");
+    let reason = matches.value_of("reason").unwrap_or("No reason given.");
+    let by = matches.value_of("by").unwrap_or(&user);
+    let explicit = matches.is_present("reason") || matches.is_present("until") || matches.is_present("by");
+    let record_ignore = |namespace: &str| -> shelflife::Result<()> {
+        if collection == "ignore" {
+            set_db_item_ignore(&mongo_client, namespace, reason, by, until, explicit)?;
+        }
+        Ok(())
+    };
+
     if matches.occurrences_of("all") > 0 {
         info!("Querying OKD API for namespace information...");
-        let proj_names = get_namespaces(&http_client);
+        if collection == "track" {
+            // Anything whose ignore entry ran out gets tracked again below.
+            release_expired_ignores(&mongo_client)?;
+        }
+        let proj_names = get_namespaces(&client);
         for project in proj_names.unwrap() {
-            query_known_namespace(&http_client, &mongo_client, collection, &project, true)?;
+            // One broken project shouldn't keep the rest from syncing.
+            if let Err(e) = query_known_namespace(&client, &mongo_client, &config, &sources, collection, &project, true) {
+                println!("Could not sync {}: {}. Moving on.", &project, e);
+                error!("Could not sync {}: {}", &project, e);
+                continue;
+            }
+            record_ignore(&project)?;
         }
         info!("OKD Query complete.");
     }
@@ -149,7 +235,7 @@ fn main() -> Result<()> {
         info!("Culling...");
         println!("You might want to run the -a option if you haven't already.");
         // 'False' as in DRYRUN IS DISABLED THIS IS ACTUALLY DESTRUCTIVE!
-        let _expiration = check_expiry_dates(&http_client, &mongo_client, collection, false, report); 
+        let _expiration = check_expiry_dates(&client, &mongo_client, &config, collection, false, report); 
         info!("Cull complete.");
     }
 
@@ -157,7 +243,7 @@ fn main() -> Result<()> {
         let report = matches.occurrences_of("dryrun_with_report") > 0;
         info!("Doing a dryrun cull...");
         // This is NOT destructive
-        let _expiration = check_expiry_dates(&http_client, &mongo_client, collection, true, report); 
+        let _expiration = check_expiry_dates(&client, &mongo_client, &config, collection, true, report); 
         info!("Dryrun cull complete.");
     }
 
@@ -166,14 +252,32 @@ fn main() -> Result<()> {
         remove_db_item(&mongo_client, collection, deleted)?;
     }
     
+    if let Some(snoozed) = matches.value_of("snooze") {
+        let until = until.unwrap();
+        info!("Snoozing {} until {}", &snoozed, &until);
+        snooze_db_item(&mongo_client, collection, snoozed, until, reason, by)?;
+    }
+
+    if let Some(ephemeral) = matches.value_of("ttl") {
+        let until = until.unwrap();
+        info!("Setting the TTL of {} to {}", &ephemeral, &until);
+        set_db_item_ttl(&mongo_client, collection, ephemeral, until)?;
+    }
+
+    if let Some(touched) = matches.value_of("touch") {
+        info!("Touching {}", &touched);
+        touch_db_item(&mongo_client, collection, touched, by)?;
+    }
+
     if let Some(known_namespace) = matches.value_of("known") {
         info!("Querying OKD API for: {}", &known_namespace);
-        query_known_namespace(&http_client, &mongo_client, collection, known_namespace, false)?;
+        query_known_namespace(&client, &mongo_client, &config, &sources, collection, known_namespace, false)?;
+        record_ignore(known_namespace)?;
     }
 
     if let Some(file) = matches.value_of("file") {
         info!("Importing projects from CSV");
-        // import_from_file(&mongo_client, &http_client, file, collection);
+        // import_from_file(&mongo_client, &client, file, collection);
         
         // File hosts must exist in current path before this produces output
         if let Ok(lines) = read_lines(file) {
@@ -182,8 +286,9 @@ fn main() -> Result<()> {
                 if let Ok(namespace) = line {
                     println!("Trying to {} {}...", collection, namespace);
                     info!("Trying to {} {}...", collection, namespace);
-                    match query_known_namespace(&http_client, &mongo_client, collection, &namespace, true) {
+                    match query_known_namespace(&client, &mongo_client, &config, &sources, collection, &namespace, true) {
                         Ok(()) => {
+                            record_ignore(&namespace)?;
                             println!("Ok.");
                             info!("Ok.")
                         },
@@ -207,13 +312,21 @@ fn main() -> Result<()> {
 
     if let Some(project_name) = matches.value_of("project") {
         info!("Querying OKD API for details about: {}", &project_name);
-        let call = format!("https://{}/oapi/v1/projects/{}", endpoint, project_name);
-        let result = get_call_api(&http_client, &call)?;
+        let result = client.project(project_name)?;
         dbg!(result);
     }
 
     if matches.occurrences_of("list") > 0 {
-        view_db(&mongo_client, collection)?;
+        view_db(&mongo_client, &config, collection)?;
+    }
+
+    if let Some(explained) = matches.value_of("explain") {
+        info!("Explaining {}", &explained);
+        explain_namespace(&client, &mongo_client, &config, collection, explained)?;
+    }
+
+    if let Some(address) = matches.value_of("serve") {
+        heartbeat::serve(&client, &mongo_client, address)?;
     }
 
     Ok(())
@@ -0,0 +1,648 @@
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use reqwest::header::RETRY_AFTER;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::auth::{self, Credentials, Tls, TlsConfig};
+use crate::protocol::*;
+use crate::Result;
+
+// ------------------------------
+// Config for how ShelfLife uses the OKD API
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    // How many items to ask for per request on list calls. 0 asks for
+    // everything at once, like ShelfLife used to.
+    pub page_size: u32,
+    // How many more times to try a call that got a 429, a 5xx, or no answer
+    // at all. The wait starts at backoff_ms and doubles every time, unless the
+    // API server says how long to wait with Retry-After.
+    pub retries: u32,
+    pub backoff_ms: u64,
+    // Most requests per second to send, so a full sync doesn't hammer the API
+    // server. 0 means no limit.
+    pub qps: f64,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        ApiConfig {
+            page_size: 500,
+            retries: 3,
+            backoff_ms: 500,
+            qps: 10.0,
+        }
+    }
+}
+
+// Never wait longer than this between tries, whatever Retry-After says.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+// ------------------------------
+
+// Everything ShelfLife needs to talk to the OKD API: where it is, who we are,
+// and the HTTP client (with its TLS settings) to get there with. Point one at
+// anything that talks like OKD, like a mock server on http://127.0.0.1.
+pub struct OkdClient {
+    http_client: reqwest::Client,
+    guest_client: reqwest::Client, // Same trust, but without our client certificate
+    base_url: String, // Scheme, host, and port. No trailing slash.
+    token: String,
+    token_file: Option<PathBuf>,
+    tls: Tls, // Kept around for writing out kubeconfigs
+    api: ApiConfig,
+    next_request: Mutex<Instant>, // When the QPS limit lets us send another one
+}
+
+impl OkdClient {
+    pub fn new(base_url: &str, token: &str) -> Result<OkdClient> {
+        OkdClient::from_credentials(auth::from_token(base_url, token))
+    }
+
+    // Uses an HTTP client you already built, with no TLS settings of its own.
+    // Handy for pointing at a test server.
+    pub fn with_http_client(http_client: reqwest::Client, base_url: &str, token: &str) -> OkdClient {
+        OkdClient {
+            guest_client: http_client.clone(),
+            http_client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            token_file: None,
+            tls: Tls::default(),
+            api: ApiConfig::default(),
+            next_request: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn from_credentials(credentials: Credentials) -> Result<OkdClient> {
+        if credentials.tls.insecure {
+            println!("WARNING: Not verifying {}'s certificate! Anybody in the middle can see our token.", &credentials.base_url);
+            warn!("TLS verification is OFF for {}. Don't do this outside of a lab cluster.", &credentials.base_url);
+        }
+        Ok(OkdClient {
+            http_client: credentials.tls.http_client(true)?,
+            guest_client: credentials.tls.http_client(false)?,
+            base_url: credentials.base_url.trim_end_matches('/').to_string(),
+            token: credentials.token,
+            token_file: credentials.token_file,
+            tls: credentials.tls,
+            api: ApiConfig::default(),
+            next_request: Mutex::new(Instant::now()),
+        })
+    }
+
+    // Logs in with whatever the environment offers (see auth::from_env), with
+    // the config file's TLS settings on top.
+    pub fn from_env(tls: &TlsConfig) -> Result<OkdClient> {
+        let mut credentials = auth::from_env()?;
+        credentials.tls.apply(tls)?;
+        OkdClient::from_credentials(credentials)
+    }
+
+    pub fn with_api_config(mut self, api: ApiConfig) -> OkdClient {
+        self.api = api;
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    // Empty if we're logging in with a client certificate instead.
+    pub fn token(&self) -> String {
+        // Keep using the old one if the new one can't be read for some reason.
+        match &self.token_file {
+            Some(file) => match fs::read_to_string(file) {
+                Ok(token) => token.trim().to_string(),
+                Err(_) => self.token.clone(),
+            },
+            None => self.token.clone(),
+        }
+    }
+
+    // Writes out a kubeconfig that logs in exactly the way we do, so tools like
+    // oc talk to the same cluster, as the same user, trusting the same CAs.
+    // It has our credentials in it, so only we can read it. Delete it when done.
+    pub fn write_kubeconfig(&self) -> Result<PathBuf> {
+        let path = env::temp_dir().join(format!("shelflife-{}.kubeconfig", process::id()));
+        let _ = fs::remove_file(&path); // Left over from a crash, maybe.
+        let mut file = OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path)?;
+        file.write_all(auth::kubeconfig(&self.base_url, &self.token(), &self.tls)?.as_bytes())?;
+        Ok(path)
+    }
+
+    /*                                     RAW API CALLS  */
+    /*  --------------------------------------------------  */
+
+    pub fn get(&self, path: &str) -> Result<reqwest::Response> {
+        let call = format!("{}{}", &self.base_url, path);
+        let response = self.send(&call, || self.authorize(self.http_client.get(&call)))?;
+        OkdClient::check(&call, response)
+    }
+
+    // Makes a call to something next to OKD, like Prometheus, with the same
+    // rate limit and retries as our own calls. Only sends the token it's given.
+    pub fn get_url(&self, url: &str, query: &[(&str, &str)], token: Option<&str>) -> Result<reqwest::Response> {
+        let response = self.send(url, || {
+            let request = self.http_client.get(url).query(query);
+            match token {
+                Some(token) => request.header("Authorization", format!("Bearer {}", token)),
+                None => request,
+            }
+        })?;
+        OkdClient::check(url, response)
+    }
+
+    // Makes a call as somebody else, to see what they're allowed to do.
+    pub fn get_as(&self, path: &str, token: &str) -> Result<reqwest::Response> {
+        let call = format!("{}{}", &self.base_url, path);
+        let response = self.send(&call, || {
+            self.guest_client
+                .get(&call)
+                .header("Authorization", format!("Bearer {}", token))
+        })?;
+        OkdClient::check(&call, response)
+    }
+
+    // Ensure the call was successful
+    fn check(call: &str, response: reqwest::Response) -> Result<reqwest::Response> {
+        if response.status() == StatusCode::OK {
+            Ok(response)
+        } else {
+            Err(From::from(format!(
+                "Error! Could not run API call. Call: {}, Code: {}", call, response.status()),
+            ))
+        }
+    }
+
+    pub fn put(&self, path: &str, body: String) -> Result<reqwest::Response> {
+        let call = format!("{}{}", &self.base_url, path);
+        let response = self.send(&call, || self.authorize(self.http_client.put(&call)).body(body.clone()))?;
+
+        if response.status() == StatusCode::OK {
+            Ok(response)
+        } else {
+            error!("Could not run API call. Call: {}, Code: {}", call, response.status());
+            Err(From::from(format!(
+                "Error: Could not run API call. Call: {}, Code: {}", call, response.status()),
+            ))
+        }
+    }
+
+    pub fn delete(&self, path: &str) -> Result<reqwest::Response> {
+        let call = format!("{}{}", &self.base_url, path);
+        let (response, retried) = self.send_counting(&call, || self.authorize(self.http_client.delete(&call)))?;
+
+        if response.status() == StatusCode::OK {
+            Ok(response)
+        } else if response.status() == StatusCode::NOT_FOUND && retried > 0 {
+            // An earlier try went through, and we just never heard back.
+            info!("{} was already gone after {} tries. Calling it deleted.", call, retried + 1);
+            Ok(response)
+        } else {
+            error!("Could not run API call. Call: {}, Code: {}", call, response.status());
+            Err(From::from(format!(
+                "Error: Could not run API call. Call: {}, Code: {}", call, response.status()),
+            ))
+        }
+    }
+
+    // Sends a request once the QPS limit allows it, and tries again if the API
+    // server hiccups. Every call we make is a GET, a PUT, or a DELETE, so
+    // sending one twice never hurts, though a DELETE that already went through
+    // comes back 404 the second time. Don't use this for a POST.
+    fn send<F: Fn() -> reqwest::RequestBuilder>(&self, call: &str, request: F) -> Result<reqwest::Response> {
+        Ok(self.send_counting(call, request)?.0)
+    }
+
+    // Same as send, but also says how many times it had to try again.
+    fn send_counting<F: Fn() -> reqwest::RequestBuilder>(&self, call: &str, request: F) -> Result<(reqwest::Response, u32)> {
+        let mut attempt = 0;
+        loop {
+            self.throttle();
+            let result = request().send();
+            let (reason, wait) = match &result {
+                Ok(response) if retryable(response.status()) => (
+                    response.status().to_string(),
+                    retry_after(response).unwrap_or_else(|| self.backoff(attempt)),
+                ),
+                Ok(_) => return Ok((result?, attempt)),
+                Err(e) => (e.to_string(), self.backoff(attempt)),
+            };
+            if attempt >= self.api.retries {
+                return Ok((result?, attempt));
+            }
+            attempt += 1;
+            warn!("API call {} failed ({}). Try {} of {} in {}ms.", call, reason, attempt, self.api.retries, wait.as_millis());
+            thread::sleep(wait);
+        }
+    }
+
+    // Waits until we're allowed to send another request.
+    fn throttle(&self) {
+        if self.api.qps <= 0.0 {
+            return;
+        }
+        let interval = Duration::from_micros((1_000_000.0 / self.api.qps) as u64);
+        let mut next_request = self.next_request.lock().unwrap();
+        let now = Instant::now();
+        if *next_request > now {
+            thread::sleep(*next_request - now);
+        }
+        *next_request = Instant::now() + interval;
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = Duration::from_millis(self.api.backoff_ms.saturating_mul(1 << attempt.min(16)));
+        backoff.min(MAX_BACKOFF)
+    }
+
+    // Client certificates don't need a token, so don't send an empty one.
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let token = self.token();
+        if token == "" {
+            request
+        } else {
+            request.header("Authorization", format!("Bearer {}", token))
+        }
+    }
+
+    fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        Ok(self.get(path)?.json()?)
+    }
+
+    // Gets a whole list, a page at a time.
+    fn list<T: List>(&self, path: &str) -> Result<T> {
+        let first_page = || -> Result<T> {
+            Ok(self.get_page(path, None)?.ok_or("Error: The first page of a list can't expire.")?)
+        };
+        // Continue tokens only last a few minutes. If one runs out partway
+        // through, start over from the top, but only once.
+        let mut restarted = false;
+        let mut list: T = first_page()?;
+        while let Some(token) = list.continue_token().map(String::from) {
+            match self.get_page(path, Some(&token))? {
+                Some(page) => list.extend(page),
+                None if !restarted => {
+                    warn!("The continue token for {} expired. Listing it again from the start.", path);
+                    restarted = true;
+                    list = first_page()?;
+                },
+                None => {
+                    return Err(From::from(format!("Error: The continue token for {} expired twice. Giving up.", path)));
+                },
+            }
+        }
+        Ok(list)
+    }
+
+    // None if the continue token has expired (410 Gone).
+    fn get_page<T: List>(&self, path: &str, continue_token: Option<&str>) -> Result<Option<T>> {
+        let call = format!("{}{}", &self.base_url, path);
+        let mut query = Vec::new();
+        if self.api.page_size > 0 {
+            query.push(("limit", self.api.page_size.to_string()));
+        }
+        if let Some(token) = continue_token {
+            query.push(("continue", token.to_string()));
+        }
+        let response = self.send(&call, || self.authorize(self.http_client.get(&call).query(&query)))?;
+        if continue_token.is_some() && response.status() == StatusCode::GONE {
+            return Ok(None);
+        }
+        Ok(Some(OkdClient::check(&call, response)?.json()?))
+    }
+
+    /*                                   TYPED API CALLS  */
+    /*  --------------------------------------------------  */
+
+    pub fn projects(&self) -> Result<ProjectResponse> {
+        self.list("/apis/project.openshift.io/v1/projects")
+    }
+
+    pub fn project(&self, namespace: &str) -> Result<ProjectItem> {
+        self.get_json(&format!("/apis/project.openshift.io/v1/projects/{}", namespace))
+    }
+
+    pub fn builds(&self, namespace: &str) -> Result<BuildlistResponse> {
+        self.list(&format!("/apis/build.openshift.io/v1/namespaces/{}/builds", namespace))
+    }
+
+    pub fn deployment_configs(&self, namespace: &str) -> Result<DeploymentResponse> {
+        self.list(&format!("/apis/apps.openshift.io/v1/namespaces/{}/deploymentconfigs", namespace))
+    }
+
+    pub fn deployments(&self, namespace: &str) -> Result<AppsDeploymentResponse> {
+        self.list(&format!("/apis/apps/v1/namespaces/{}/deployments", namespace))
+    }
+
+    pub fn stateful_sets(&self, namespace: &str) -> Result<StatefulSetResponse> {
+        self.list(&format!("/apis/apps/v1/namespaces/{}/statefulsets", namespace))
+    }
+
+    pub fn cron_jobs(&self, namespace: &str) -> Result<CronJobResponse> {
+        self.list(&format!("/apis/batch/v1beta1/namespaces/{}/cronjobs", namespace))
+    }
+
+    pub fn pods(&self, namespace: &str) -> Result<PodsResponse> {
+        self.list(&format!("/api/v1/namespaces/{}/pods", namespace))
+    }
+
+    pub fn image_streams(&self, namespace: &str) -> Result<ImageStreamResponse> {
+        self.list(&format!("/apis/image.openshift.io/v1/namespaces/{}/imagestreams", namespace))
+    }
+
+    pub fn events(&self, namespace: &str) -> Result<EventResponse> {
+        self.list(&format!("/api/v1/namespaces/{}/events", namespace))
+    }
+
+    pub fn routes(&self, namespace: &str) -> Result<RouteResponse> {
+        self.list(&format!("/apis/route.openshift.io/v1/namespaces/{}/routes", namespace))
+    }
+
+    pub fn rolebindings(&self, namespace: &str) -> Result<RolebindingsResponse> {
+        self.list(&format!("/apis/authorization.openshift.io/v1/namespaces/{}/rolebindings", namespace))
+    }
+
+    // Who a token belongs to.
+    pub fn whoami_as(&self, token: &str) -> Result<UserItem> {
+        Ok(self.get_as("/apis/user.openshift.io/v1/users/~", token)?.json()?)
+    }
+
+    // Tells a deploymentconfig how many pods to run.
+    pub fn scale(&self, namespace: &str, deployment_config: &str, replicas: u32) -> Result<()> {
+        let path = format!("/apis/apps.openshift.io/v1/namespaces/{}/deploymentconfigs/{}/scale", namespace, deployment_config);
+        let post = format!(
+            "{{\"apiVersion\":\"extensions/v1beta1\",\"kind\":\"Scale\",\"metadata\":{{\"name\":\"{}\",\"namespace\":\"{}\"}},\"spec\":{{\"replicas\":{}}}}}",
+            deployment_config, namespace, replicas);
+        self.put(&path, post)?;
+        Ok(())
+    }
+
+    pub fn delete_project(&self, namespace: &str) -> Result<()> {
+        self.delete(&format!("/apis/project.openshift.io/v1/projects/{}", namespace))?;
+        Ok(())
+    }
+}
+
+// Too many requests, or the API server (or something in front of it) fell over.
+fn retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+// Retry-After is either a number of seconds or an HTTP date.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    let wait = match value.trim().parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+            (at - Utc::now()).to_std().unwrap_or(Duration::from_secs(0))
+        },
+    };
+    Some(wait.min(MAX_BACKOFF))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    // A stand-in API server. Answers one connection per canned response, in
+    // order, then hands back the requests it got. Other modules' tests use
+    // these too.
+    pub(crate) fn serve(responses: Vec<String>) -> (String, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                requests.push(read_request(&mut stream));
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (base_url, server)
+    }
+
+    fn read_request(stream: &mut impl Read) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text.lines()
+                    .find(|line| line.to_lowercase().starts_with("content-length:"))
+                    .and_then(|line| line[15..].trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if read == 0 || request.len() >= end + 4 + length {
+                    return text;
+                }
+            } else if read == 0 {
+                return text;
+            }
+        }
+    }
+
+    pub(crate) fn response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status, headers, body.len(), body,
+        )
+    }
+
+    fn projects(names: &[&str], continue_token: Option<&str>) -> String {
+        let items: Vec<String> = names.iter()
+            .map(|name| format!(
+                "{{\"metadata\":{{\"name\":\"{}\",\"creationTimestamp\":\"2020-03-10T12:00:00Z\"}}}}", name,
+            ))
+            .collect();
+        let metadata = match continue_token {
+            Some(token) => format!("{{\"continue\":\"{}\"}}", token),
+            None => "{}".to_string(),
+        };
+        format!("{{\"metadata\":{},\"items\":[{}]}}", metadata, items.join(","))
+    }
+
+    pub(crate) fn client(base_url: &str, api: ApiConfig) -> OkdClient {
+        OkdClient::with_http_client(reqwest::Client::new(), base_url, "secret").with_api_config(api)
+    }
+
+    // No rate limit and hardly any waiting, so the tests stay quick.
+    pub(crate) fn quick() -> ApiConfig {
+        ApiConfig { page_size: 0, retries: 2, backoff_ms: 1, qps: 0.0 }
+    }
+
+    #[test]
+    fn projects_are_listed_with_our_token() {
+        let (base_url, server) = serve(vec![response("200 OK", "", &projects(&["alpha", "beta"], None))]);
+        let names: Vec<String> = client(&base_url, quick()).projects().unwrap()
+            .items.into_iter().map(|p| p.metadata.name).collect();
+        assert_eq!(names, vec!["alpha", "beta"]);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /apis/project.openshift.io/v1/projects HTTP/1.1"));
+        assert!(requests[0].to_lowercase().contains("authorization: bearer secret"));
+    }
+
+    #[test]
+    fn projects_are_stitched_together_from_pages() {
+        let (base_url, server) = serve(vec![
+            response("200 OK", "", &projects(&["alpha"], Some("next-page"))),
+            response("200 OK", "", &projects(&["beta"], None)),
+        ]);
+        let api = ApiConfig { page_size: 1, ..quick() };
+        let names: Vec<String> = client(&base_url, api).projects().unwrap()
+            .items.into_iter().map(|p| p.metadata.name).collect();
+        assert_eq!(names, vec!["alpha", "beta"]);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /apis/project.openshift.io/v1/projects?limit=1 "));
+        assert!(requests[1].starts_with("GET /apis/project.openshift.io/v1/projects?limit=1&continue=next-page "));
+    }
+
+    #[test]
+    fn an_expired_continue_token_starts_the_list_over_once() {
+        let (base_url, server) = serve(vec![
+            response("200 OK", "", &projects(&["alpha"], Some("stale"))),
+            response("410 Gone", "", "{}"),
+            response("200 OK", "", &projects(&["alpha"], Some("fresh"))),
+            response("200 OK", "", &projects(&["beta"], None)),
+        ]);
+        let api = ApiConfig { page_size: 1, ..quick() };
+        let names: Vec<String> = client(&base_url, api).projects().unwrap()
+            .items.into_iter().map(|p| p.metadata.name).collect();
+        assert_eq!(names, vec!["alpha", "beta"]);
+
+        let requests = server.join().unwrap();
+        assert!(requests[1].starts_with("GET /apis/project.openshift.io/v1/projects?limit=1&continue=stale "));
+        assert!(requests[2].starts_with("GET /apis/project.openshift.io/v1/projects?limit=1 "));
+        assert!(requests[3].starts_with("GET /apis/project.openshift.io/v1/projects?limit=1&continue=fresh "));
+    }
+
+    #[test]
+    fn a_continue_token_that_keeps_expiring_is_an_error() {
+        let (base_url, server) = serve(vec![
+            response("200 OK", "", &projects(&["alpha"], Some("stale"))),
+            response("410 Gone", "", "{}"),
+            response("200 OK", "", &projects(&["alpha"], Some("stale-again"))),
+            response("410 Gone", "", "{}"),
+        ]);
+        let api = ApiConfig { page_size: 1, ..quick() };
+        assert!(client(&base_url, api).projects().is_err());
+        assert_eq!(server.join().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn error_statuses_are_errors() {
+        // Not worth retrying either, so only one request goes out.
+        let (base_url, server) = serve(vec![response("403 Forbidden", "", "{}")]);
+        let error = client(&base_url, quick()).projects().unwrap_err();
+        assert!(error.to_string().contains("403"));
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn scale_puts_to_the_deploymentconfig() {
+        let (base_url, server) = serve(vec![response("200 OK", "", "{}")]);
+        client(&base_url, quick()).scale("myproject", "myapp", 0).unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with(
+            "PUT /apis/apps.openshift.io/v1/namespaces/myproject/deploymentconfigs/myapp/scale HTTP/1.1"
+        ));
+        assert!(requests[0].ends_with("\"spec\":{\"replicas\":0}}"));
+    }
+
+    #[test]
+    fn server_errors_are_retried() {
+        let (base_url, server) = serve(vec![
+            response("503 Service Unavailable", "Retry-After: 0\r\n", "{}"),
+            response("500 Internal Server Error", "", "{}"),
+            response("200 OK", "", &projects(&["alpha"], None)),
+        ]);
+        let list = client(&base_url, quick()).projects().unwrap();
+        assert_eq!(list.items.len(), 1);
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn a_retried_delete_that_already_went_through_is_fine() {
+        let (base_url, server) = serve(vec![
+            response("504 Gateway Timeout", "", "{}"),
+            response("404 Not Found", "", "{}"),
+        ]);
+        client(&base_url, quick()).delete_project("myproject").unwrap();
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("DELETE /apis/project.openshift.io/v1/projects/myproject HTTP/1.1"));
+    }
+
+    #[test]
+    fn deleting_something_that_was_never_there_is_an_error() {
+        let (base_url, server) = serve(vec![response("404 Not Found", "", "{}")]);
+        assert!(client(&base_url, quick()).delete_project("myproject").is_err());
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn retries_run_out() {
+        let (base_url, server) = serve(vec![
+            response("429 Too Many Requests", "", "{}"),
+            response("429 Too Many Requests", "", "{}"),
+        ]);
+        let api = ApiConfig { retries: 1, ..quick() };
+        assert!(client(&base_url, api).projects().is_err());
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn retry_after_takes_seconds_or_a_date() {
+        let soon = (Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+        let (base_url, server) = serve(vec![
+            response("503 Service Unavailable", "Retry-After: 2\r\n", ""),
+            response("503 Service Unavailable", "Retry-After: 3600\r\n", ""),
+            response("503 Service Unavailable", &format!("Retry-After: {}\r\n", soon), ""),
+            response("503 Service Unavailable", "Retry-After: Sun, 06 Nov 1994 08:49:37 GMT\r\n", ""),
+            response("503 Service Unavailable", "Retry-After: whenever\r\n", ""),
+            response("503 Service Unavailable", "", ""),
+        ]);
+        let http_client = reqwest::Client::new();
+        let mut waits = Vec::new();
+        for _ in 0..6 {
+            waits.push(retry_after(&http_client.get(&base_url).send().unwrap()));
+        }
+        server.join().unwrap();
+
+        assert_eq!(waits[0], Some(Duration::from_secs(2)));
+        assert_eq!(waits[1], Some(MAX_BACKOFF));
+        let wait = waits[2].unwrap();
+        assert!(wait > Duration::from_secs(25) && wait <= Duration::from_secs(30));
+        assert_eq!(waits[3], Some(Duration::from_secs(0)));
+        assert_eq!(waits[4], None);
+        assert_eq!(waits[5], None);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let client = client("http://127.0.0.1", ApiConfig { backoff_ms: 500, ..quick() });
+        assert_eq!(client.backoff(0), Duration::from_millis(500));
+        assert_eq!(client.backoff(1), Duration::from_millis(1000));
+        assert_eq!(client.backoff(3), Duration::from_millis(4000));
+        assert_eq!(client.backoff(10), MAX_BACKOFF);
+        assert_eq!(client.backoff(u32::MAX), MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,64 @@
+use serde::Deserialize;
+use std::env;
+use std::fs::File;
+use crate::policy::{Policy, Protection, Window};
+use crate::activity::ActivityConfig;
+use crate::auth::TlsConfig;
+use crate::client::ApiConfig;
+use crate::usage::UsageConfig;
+use crate::Result;
+
+// Everything ShelfLife reads from its config file. Every section has a
+// default, so a missing file just means "do what ShelfLife has always done."
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub policy: Policy,
+    // Nothing gets spun down or archived during these. Nudges still go out.
+    pub blackouts: Vec<Window>,
+    // Time spent in these doesn't count toward a project's age.
+    pub pauses: Vec<Window>,
+    // Namespaces that must never be tracked or culled.
+    pub protected: Protection,
+    // Which activity sources count toward a project's last update.
+    pub activity: ActivityConfig,
+    // Where to look up CPU and network usage, and what counts as idle.
+    pub usage: UsageConfig,
+    // Extra CAs, client certificates, and the like for the OKD API.
+    pub tls: TlsConfig,
+    // How hard ShelfLife leans on the OKD API.
+    pub api: ApiConfig,
+}
+
+// Loads the YAML config file pointed to by CONFIG_PATH.
+pub fn load_config() -> Result<Config> {
+    let mut config = match env::var("CONFIG_PATH") {
+        Ok(path) if path != "" => {
+            info!("Loading config from {}", &path);
+            let file = File::open(&path)?;
+            let config: Config = serde_yaml::from_reader(file)?;
+            config
+        },
+        _ => {
+            println!("No CONFIG_PATH set. Using the default policy.");
+            info!("No CONFIG_PATH set. Using the default policy.");
+            Config::default()
+        },
+    };
+    config.policy.sort();
+    config.validate()?;
+    Ok(config)
+}
+
+impl Config {
+    // Catches anything that would otherwise only turn up halfway through a
+    // cull, so ShelfLife refuses to start instead.
+    fn validate(&mut self) -> Result<()> {
+        self.policy.validate()?;
+        self.protected.compile()?;
+        for window in self.blackouts.iter().chain(self.pauses.iter()) {
+            window.validate()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use crate::client::OkdClient;
+use crate::{touch_db_item, Result};
+
+// A tiny HTTP endpoint so a project can keep itself alive on purpose, e.g.
+// from a cron job inside it:
+//
+// curl -X POST -H "Authorization: Bearer $(cat /var/run/secrets/kubernetes.io/serviceaccount/token)" \
+//     https://shelflife.example.com/touch/myproject
+//
+// The caller's OKD token has to be able to see the project. Whoever it belongs
+// to goes down as who touched it. This only speaks plain HTTP, and those tokens
+// are worth stealing, so only ever run it behind an edge-terminated route.
+pub fn serve(client: &OkdClient, mongo_client: &mongodb::Client, address: &str) -> Result<()> {
+    let listener = TcpListener::bind(address)?;
+    println!("Listening for heartbeats on {}...", address);
+    info!("Listening for heartbeats on {}", address);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let touch = |namespace: &str, by: &str| touch_db_item(mongo_client, "track", namespace, by);
+                if let Err(e) = handle(client, stream, touch) {
+                    warn!("Heartbeat request failed: {}", e);
+                }
+            },
+            Err(e) => warn!("Could not accept heartbeat connection: {}", e),
+        }
+    }
+    Ok(())
+}
+
+// Answers one request. `touch` records the heartbeat, and fails if the project
+// isn't tracked.
+fn handle<F: Fn(&str, &str) -> Result<()>>(client: &OkdClient, mut stream: TcpStream, touch: F) -> Result<()> {
+    // Don't let one slow client hold everybody else up.
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    // All we care about in the headers is the token.
+    let mut token = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let mut header = line.splitn(2, ':');
+        let name = header.next().unwrap_or_default().trim();
+        let value = header.next().unwrap_or_default().trim();
+        if name.eq_ignore_ascii_case("authorization") && value.starts_with("Bearer ") {
+            token = Some(value.trim_start_matches("Bearer ").trim().to_string());
+        }
+    }
+
+    let namespace = match path.trim_end_matches('/').splitn(3, '/').collect::<Vec<&str>>().as_slice() {
+        ["", "touch", namespace] if valid_namespace(namespace) => namespace.to_string(),
+        _ => return respond(&mut stream, "404 Not Found", "Try POST /touch/<namespace>."),
+    };
+    if method != "POST" {
+        return respond(&mut stream, "405 Method Not Allowed", "Heartbeats have to be POSTed.");
+    }
+    let token = match token {
+        Some(token) => token,
+        None => return respond(&mut stream, "401 Unauthorized", "Missing bearer token."),
+    };
+
+    let user = match client.whoami_as(&token) {
+        Ok(user) => user,
+        Err(_) => return respond(&mut stream, "401 Unauthorized", "OKD didn't accept that token."),
+    };
+    let project_call = format!("/apis/project.openshift.io/v1/projects/{}", &namespace);
+    if client.get_as(&project_call, &token).is_err() {
+        warn!("{} tried to touch {} without access to it.", &user.metadata.name, &namespace);
+        return respond(&mut stream, "403 Forbidden", "That token can't see this project.");
+    }
+
+    match touch(&namespace, &user.metadata.name) {
+        Ok(()) => respond(&mut stream, "200 OK", "Touched."),
+        Err(e) => {
+            warn!("Could not touch {}: {}", &namespace, e);
+            respond(&mut stream, "404 Not Found", "ShelfLife isn't tracking that project.")
+        },
+    }
+}
+
+// Namespaces are DNS labels. Anything else doesn't belong in an API call.
+fn valid_namespace(namespace: &str) -> bool {
+    !namespace.is_empty() && namespace.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}\n",
+        status, body.len() + 1, body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::tests::{client, quick, response, serve};
+    use std::cell::RefCell;
+    use std::io::Read;
+
+    const WHOAMI: &str = "{\"metadata\":{\"name\":\"alice\"}}";
+
+    // Sends a request to handle, and returns what it answered and who it touched.
+    fn request(api: Vec<String>, request: &str) -> (String, Vec<(String, String)>) {
+        let (base_url, server) = serve(api);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut caller = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        caller.write_all(request.as_bytes()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        let touched = RefCell::new(Vec::new());
+        let touch = |namespace: &str, by: &str| -> Result<()> {
+            if namespace != "myproject" {
+                return Err(From::from(format!("{} is not in the track table.", namespace)));
+            }
+            touched.borrow_mut().push((namespace.to_string(), by.to_string()));
+            Ok(())
+        };
+        handle(&client(&base_url, quick()), stream, touch).unwrap();
+
+        let mut answer = String::new();
+        caller.read_to_string(&mut answer).unwrap();
+        server.join().unwrap();
+        (answer, touched.into_inner())
+    }
+
+    fn post(path: &str, token: &str) -> String {
+        format!("POST {} HTTP/1.1\r\nHost: shelflife\r\nAuthorization: Bearer {}\r\n\r\n", path, token)
+    }
+
+    #[test]
+    fn heartbeats_touch_the_project_as_the_caller() {
+        let api = vec![response("200 OK", "", WHOAMI), response("200 OK", "", "{}")];
+        let (answer, touched) = request(api, &post("/touch/myproject", "alices-token"));
+        assert!(answer.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(touched, vec![("myproject".to_string(), "alice".to_string())]);
+    }
+
+    #[test]
+    fn heartbeats_need_a_token_okd_takes() {
+        let (answer, touched) = request(vec![], "POST /touch/myproject HTTP/1.1\r\nHost: shelflife\r\n\r\n");
+        assert!(answer.starts_with("HTTP/1.1 401"));
+        assert!(touched.is_empty());
+
+        let api = vec![response("401 Unauthorized", "", "{}")];
+        let (answer, touched) = request(api, &post("/touch/myproject", "bad-token"));
+        assert!(answer.starts_with("HTTP/1.1 401"));
+        assert!(touched.is_empty());
+    }
+
+    #[test]
+    fn heartbeats_need_access_to_the_project() {
+        let api = vec![response("200 OK", "", WHOAMI), response("403 Forbidden", "", "{}")];
+        let (answer, touched) = request(api, &post("/touch/myproject", "alices-token"));
+        assert!(answer.starts_with("HTTP/1.1 403"));
+        assert!(touched.is_empty());
+    }
+
+    #[test]
+    fn heartbeats_for_untracked_projects_are_not_found() {
+        let api = vec![response("200 OK", "", WHOAMI), response("200 OK", "", "{}")];
+        let (answer, touched) = request(api, &post("/touch/otherproject", "alices-token"));
+        assert!(answer.starts_with("HTTP/1.1 404"));
+        assert!(touched.is_empty());
+
+        let (answer, _) = request(vec![], &post("/touch/Not_A_Namespace", "alices-token"));
+        assert!(answer.starts_with("HTTP/1.1 404"));
+        let (answer, _) = request(vec![], &post("/elsewhere", "alices-token"));
+        assert!(answer.starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn heartbeats_have_to_be_posted() {
+        let get = "GET /touch/myproject HTTP/1.1\r\nAuthorization: Bearer alices-token\r\n\r\n";
+        let (answer, touched) = request(vec![], get);
+        assert!(answer.starts_with("HTTP/1.1 405"));
+        assert!(touched.is_empty());
+    }
+}
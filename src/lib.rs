@@ -2,8 +2,16 @@ extern crate mongodb;
 extern crate reqwest;
 #[macro_use] extern crate prettytable;
 #[macro_use] extern crate log;
+#[macro_use] extern crate lazy_static;
 
 pub mod protocol;
+pub mod auth;
+pub mod client;
+pub mod config;
+pub mod policy;
+pub mod activity;
+pub mod usage;
+pub mod heartbeat;
 extern crate lettre;
 extern crate lettre_email;
 extern crate dotenv;
@@ -12,22 +20,30 @@ use mongodb::db::ThreadedDatabase;
 use mongodb::{bson, doc, Bson, ThreadedClient};
 use prettytable::Table;
 use protocol::*;
-use reqwest::StatusCode;
-use chrono::{DateTime, Duration, Utc};
+use config::Config;
+use client::OkdClient;
+use activity::{Evidence, Registry};
+use policy::{Action, Policy, Window, parse_date, parse_duration};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
 use lettre::smtp::authentication::{Credentials, Mechanism};
-use lettre::{Transport, SmtpClient};
+use lettre::{Transport, SmtpClient, SmtpTransport};
 use lettre::smtp::ConnectionReuseParameters;
 use lettre_email::Email;
 use std::process::Command;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 
 // TODO: Any better way to import this stuff?
 use std::env;
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
-// Let's make sure those environment variables are set, yea?
+// Let's make sure those environment variables are set, yea? OKD_TOKEN and
+// ENDPOINT aren't here, since there are other ways to log in. auth::from_env
+// sorts that out.
 pub fn check_env() { // TODO: Actually use results.
-    let variables = vec!("OKD_TOKEN", "DB_ADDR", "DB_PORT", "SEND_MAIL", "MAIL_ROOT", "MAIL_ROOT_ADDR", "EMAIL_SRV","EMAIL_UNAME", "EMAIL_PASSWD", "EMAIL_ADDRESS", "EMAIL_DOMAIN", "BACKUP_PATH", "LOG_PATH"); 
+    let variables = vec!("DB_ADDR", "DB_PORT", "SEND_MAIL", "MAIL_ROOT", "MAIL_ROOT_ADDR", "EMAIL_SRV","EMAIL_UNAME", "EMAIL_PASSWD", "EMAIL_ADDRESS", "EMAIL_DOMAIN", "BACKUP_PATH", "LOG_PATH"); 
 
     for i in variables {
         match env::var(i) {
@@ -54,46 +70,36 @@ pub fn check_env() { // TODO: Actually use results.
 /*                                  PROJECT FUNCTIONS  */
 /* --------------------------------------------------  */
 
-pub fn get_namespaces(http_client: &reqwest::Client) -> Result<Vec<String>> {
-    let endpoint = env::var("ENDPOINT")?; 
-    let projects_call = format!("https://{}/apis/project.openshift.io/v1/projects", endpoint); 
-    let projects_resp = get_call_api(&http_client, &projects_call);
-
-    match projects_resp {
-        Ok(mut call_reply) => {
-            let mut projects = Vec::new();
-            let projects_json: ProjectResponse = call_reply.json()?;
-            
-            for item in projects_json.items {
-                projects.push(item.metadata.name);
-            }
-            dbg!(&projects);
-            return Ok(projects)
-        },
-        Err(e) => return Err(e)
+pub fn get_namespaces(client: &OkdClient) -> Result<Vec<String>> {
+    let projects_json = client.projects()?;
+    let mut projects = Vec::new();
+    for item in projects_json.items {
+        projects.push(item.metadata.name);
     }
+    dbg!(&projects);
+    Ok(projects)
 }
 
 //Queries API for a project namespace name 
 pub fn query_known_namespace(
-    http_client: &reqwest::Client,
+    client: &OkdClient,
     mongo_client: &mongodb::Client,
+    config: &Config,
+    sources: &Registry,
     collection: &str,
     namespace: &str,
     autoadd: bool,
 ) -> Result<()> {
     // Check the MongoDB to see if we have anything by that name.
-    let current_item = match mongo_client
-                             .db("SHELFLIFE")
-                             .collection(collection)
-                             .find_one(Some(doc!{"name": namespace}), None) {
-        Ok(Some(db_result)) => {
+    match mongo_client
+              .db("SHELFLIFE")
+              .collection(collection)
+              .find_one(Some(doc!{"name": namespace}), None) {
+        Ok(Some(_)) => {
             println!("{} already discovered.", namespace);
-            Some(db_result)
         },
         Ok(None) => {
             println!("{} not yet discovered.", namespace);
-            None
         },
         Err(e) => {
             eprintln!("{}", e);
@@ -103,13 +109,33 @@ pub fn query_known_namespace(
 
     // Get all the data we need from the OpenShift API.
     println!("{}",format!("Querying API for namespace \"{}\"...", namespace).to_string());
-    let mut namespace_info = get_shelflife_info(http_client, namespace,)?;
+    let mut namespace_info = get_shelflife_info(client, sources, namespace,)?;
 
     // Query the DB and get back a table of already added namespaces
     let current_table: Vec<DBItem> = get_db(mongo_client, &collection)?;
     
     // Check if the namespace queried for is in the DB, and if not, ask to put it in.
     let queried_namespace = namespace_info.name.to_string();
+
+    // Protected namespaces never get tracked. If one somehow already is, get it out.
+    if collection == "track" {
+        let tracked = current_table.iter().any(|x| x.name == queried_namespace);
+        let protected = match tracked {
+            true => config.protected.matches(&namespace_info),
+            false => config.protected.matches_new(&namespace_info),
+        };
+        if let Some(rule) = protected {
+            println!("This namespace is protected ({}).\nSkipped.", rule);
+            warn!("{} is protected ({}). Skipped.", &queried_namespace, rule);
+            if tracked {
+                println!("It was being tracked anyway! Removing it.");
+                error!("Protected namespace {} was being tracked! Removing it.", &queried_namespace);
+                remove_db_item(mongo_client, collection, &queried_namespace)?;
+            }
+            return Ok(());
+        }
+    }
+
     if !current_table.iter().any(|x| x.name.to_string() == queried_namespace) {
         let mut add = false;
         println!("\"{}\" is not in the database. ", queried_namespace);
@@ -121,16 +147,6 @@ pub fn query_known_namespace(
                 warn!("However, it's ignored.\nSkipped.");
                 return Ok(());
             }
-            if namespace_info.admins.len() == 0 {
-                println!("This namespace has 0 admins. Assuming part of OKD.\nSkipped.");
-                warn!("This namespace has 0 admins. Assuming part of OKD.\nSkipped.");
-                return Ok(());
-            }
-            if namespace_info.name == "management-infra" || namespace_info.name == "default" {
-                println!("This looks important.\nSkipped.");
-                warn!("This looks important.\nSkipped.");
-                return Ok(());
-            }
         }
         if !autoadd {
             println!("Would you like to add it? (y/n): ");
@@ -176,20 +192,88 @@ pub fn query_known_namespace(
     } else {
         println!("The requested namespace is in the database. Updating entry...");
         info!("Updated namespace: {}", &queried_namespace);
-        // Preserve the discovery date.
-        let discovery_date = match current_item {
-            Some(item) => item.get_str("discovery_date").unwrap().to_string(),
-            None => panic!("How did you get here?"),
-        };
-        namespace_info.discovery_date = discovery_date; //TODO: I think there's an error to catch here.
-        //TODO: Update db entry instead of adding and removing it.
-        let _db_result = remove_db_item(mongo_client, collection, &queried_namespace);
-        let _table_add = add_item_to_db(mongo_client, &collection, namespace_info);
+        // Heartbeats and revivals count as activity too, but the API doesn't
+        // know about them. Keep them, and go off of them if they're still the
+        // most recent thing that happened.
+        let existing = current_table.iter().find(|x| x.name == queried_namespace);
+        if let Some(existing) = existing {
+            let kept = vec![
+                ("heartbeat", "Heartbeat", existing.heartbeat.as_ref().map(|heartbeat| &heartbeat.at)),
+                ("revived", "Revived", existing.signals.get("revived")),
+            ];
+            for (source, cause, at) in kept {
+                let at = match at {
+                    Some(at) => at,
+                    None => continue,
+                };
+                namespace_info.signals.insert(source.to_string(), at.to_string());
+                let newer = match (DateTime::parse_from_rfc2822(at), DateTime::parse_from_rfc2822(&namespace_info.last_update)) {
+                    (Ok(at), Ok(last_update)) => at > last_update,
+                    _ => false,
+                };
+                if newer {
+                    namespace_info.last_update = at.to_string();
+                    namespace_info.cause = cause.to_string();
+                }
+            }
+        }
+
+        // If somebody scaled a spun-down project back up, start its countdown over.
+        if let (Some(existing), "track") = (existing, collection) {
+            if !existing.spun_down.is_empty() {
+                match check_revival(client, existing) {
+                    Ok(true) => {
+                        let now = Utc::now().to_rfc2822();
+                        println!("{} has been revived! Resetting its stage.", &queried_namespace);
+                        info!("{} was revived after being spun down.", &queried_namespace);
+                        namespace_info.last_update = now.to_string();
+                        namespace_info.cause = "Revived".to_string();
+                        namespace_info.signals.insert("revived".to_string(), now);
+                        revive_db_item(mongo_client, collection, &queried_namespace)?;
+                        clear_db_item_notices(mongo_client, collection, existing)?;
+                        notify_revival(config, existing)?;
+                    },
+                    Ok(false) => {},
+                    Err(e) => warn!("Could not check if {} was revived: {}", &queried_namespace, e),
+                }
+            }
+        }
+        // Only touches what the API told us, so the discovery date and the
+        // project's stage are preserved.
+        update_db_item(mongo_client, collection, &namespace_info)?;
         println!("Entry updated.");
     }
     Ok(())
 }
 
+// Checks whether any of the deploymentconfigs ShelfLife scaled to 0 are
+// running again. ShelfLife never scales anything up, so somebody else did.
+fn check_revival(client: &OkdClient, item: &DBItem) -> Result<bool> {
+    let deploycfgs_json = client.deployment_configs(&item.name)?;
+    Ok(deploycfgs_json.items.iter().any(|deployment| {
+        deployment.status.replicas > 0 && item.spun_down.iter().any(|spun_down| spun_down.name == deployment.metadata.name)
+    }))
+}
+
+// Lets the admins of a revived project know its countdown started over.
+fn notify_revival(config: &Config, item: &DBItem) -> Result<()> {
+    if env::var("SEND_MAIL")? != "true" {
+        return Ok(());
+    }
+    let send_to_root = env::var("MAIL_ROOT")? == "true";
+    let email_addr = env::var("EMAIL_ADDRESS")?;
+    let email_domain = env::var("EMAIL_DOMAIN")?;
+    let mut mailer = get_mailer()?;
+    let subject = config.policy.render_revival(&config.policy.revival_subject, item);
+    let body = config.policy.render_revival(&config.policy.revival_template, item);
+    if !notify_admins(&mut mailer, &email_addr, &email_domain, send_to_root, item, &subject, &body) {
+        println!("Nobody got the revival notice for {}!", &item.name);
+        error!("Nobody got the revival notice for {}!", &item.name);
+    }
+    mailer.close();
+    Ok(())
+}
+
 //Iterates through a CSV, adding namespaces to either the tracking list or ignoring list
 // fn import_from_file(
 //     mongo_client: &mongodb::Client,
@@ -202,73 +286,40 @@ pub fn query_known_namespace(
 
 // Queries the API and returns a Struct with data relevant for shelflife's operation.
 fn get_shelflife_info(
-    http_client: &reqwest::Client,
+    client: &OkdClient,
+    sources: &Registry,
     namespace: &str,
 ) -> Result<DBItem> {
-    let endpoint = env::var("ENDPOINT")?;
-
     // Query for creation date. This is guaranteed to exist.
-    let namespace_call = format!("https://{}/apis/project.openshift.io/v1/projects/{}", endpoint, namespace); // Formulate the call
-    let namespace_resp = get_call_api(&http_client, &namespace_call); // Make the call
-    let namespace_json: ProjectItem = namespace_resp?.json()?;
-    let mut latest_update = DateTime::parse_from_rfc3339(&namespace_json.metadata.creation_timestamp)?;
-    let mut cause = "Creation";
-
-    // Query for builds
-    let builds_call = format!("https://{}/apis/build.openshift.io/v1/namespaces/{}/builds",endpoint, namespace); // Formulate the call
-    let builds_resp = get_call_api(&http_client, &builds_call); // Make the call
-    // let builds_json_result = builds_resp?.json(); // Bind json of reply to struct.
-    let mut builds = Vec::new();
-    // Get the timestamp of the last builds.
-    let builds_json: BuildlistResponse = builds_resp?.json()?;
-    for item in builds_json.items {
-        if let Some(x) = &item.status.completion_timestamp {
-            builds.push(DateTime::parse_from_rfc3339(x)?);
-        } else {
-            println!("Error fetching build timestamp.");
-        }
-    }
-    builds.sort();
+    let namespace_json = client.project(namespace)?;
 
-    // Query deployment configs
-    // Formulate the call
-    let deploycfgs_call = format!("https://{}/apis/apps.openshift.io/v1/namespaces/{}/deploymentconfigs", endpoint, namespace);
-    let deploycfgs_resp = get_call_api(&http_client, &deploycfgs_call); // Make the call
-    let deploycfgs_json: DeploymentResponse = deploycfgs_resp?.json()?; // Bind json of reply to struct.
-    // Get the timestamp of the last deployments.
-    let mut deploys = Vec::new();
-    for config in deploycfgs_json.items {
-        for condition in config.status.conditions {
-            deploys.push(DateTime::parse_from_rfc3339(&condition.last_update_time)?);
-        }
-    }
-    deploys.sort();
+    let labels = namespace_json.metadata.labels.unwrap_or_default();
 
-    if deploys.len() > 0 {
-        // If it exists, default to using latest deploymentconfig date if there are no
-        // builds available.
-        latest_update = *deploys.last().unwrap();
-        cause = "Deployment";
-    }
+    // Project admins can override the policy for their namespace with annotations.
+    let annotations = namespace_json.metadata.annotations.unwrap_or_default();
+    let overrides = Overrides {
+        expire_after: annotations.get("shelflife/expire-after").cloned(),
+        exempt_until: annotations.get("shelflife/exempt-until").cloned(),
+        stage_override: annotations.get("shelflife/stage-override").cloned(),
+        ttl: annotations.get("shelflife/ttl").cloned(),
+    };
 
-    if builds.len() > 0 {
-        // Compare the latest build date with the current latest update date, which could be
-        // either the creation date or the latest deployment date. If the latest build happened
-        // later, use that.
-        let latest_build = *builds.last().unwrap();
-        if latest_update.signed_duration_since(latest_build) < chrono::Duration::seconds(0) {
-            latest_update = latest_build;
-            cause = "Build";
-        }
-    }
+    // Ask every activity source what it knows, and go off of the winner.
+    let creation = Evidence {
+        cause: "Creation".to_string(),
+        timestamp: DateTime::parse_from_rfc3339(&namespace_json.metadata.creation_timestamp)?,
+    };
+    let found = sources.collect(client, namespace);
+    let latest = sources.latest(&found, creation.clone());
+    // Keep everything we found, not just the winner.
+    let mut signals: HashMap<String, String> = found
+        .iter()
+        .map(|(name, evidence)| (name.to_string(), evidence.timestamp.to_rfc2822()))
+        .collect();
+    signals.insert("creation".to_string(), creation.timestamp.to_rfc2822());
 
     // Query rolebindings for the admins of the namespace
-    let rolebdgs_call = format!(
-        "https://{}/apis/authorization.openshift.io/v1/namespaces/{}/rolebindings",
-        endpoint, namespace
-    );
-    let rolebdgs_resp = get_call_api(&http_client, &rolebdgs_call);
-    let rolebdgs_json: RolebindingsResponse = rolebdgs_resp?.json()?;
+    let rolebdgs_json = client.rolebindings(namespace)?;
     let rolebdgs: Vec<String> = rolebdgs_json
         .items
         .into_iter()
@@ -287,24 +338,32 @@ fn get_shelflife_info(
         name: namespace.to_string(),
         admins: rolebindings,
         discovery_date: "MISSING!".to_string(), //TODO
-        last_update: latest_update.to_rfc2822(),
-        cause: cause.to_string(), 
+        last_update: latest.timestamp.to_rfc2822(),
+        cause: latest.cause, 
+        overrides,
+        stage: String::new(),
+        stage_entered: String::new(),
+        notices: HashMap::new(),
+        snooze: None,
+        ignore: None,
+        ttl: None,
+        labels,
+        heartbeat: None,
+        signals,
+        spun_down: Vec::new(),
     };
     Ok(api_response)
 }
 
 pub fn check_expiry_dates(
-    http_client: &reqwest::Client, 
+    client: &OkdClient,
     mongo_client: &mongodb::Client, 
+    config: &Config,
     collection: &str,
     dryrun: bool,
     report: bool,
 ) -> Result<()>{
-    let endpoint = env::var("ENDPOINT")?; 
 
-    let email_srv = env::var("EMAIL_SRV")?;
-    let email_uname = env::var("EMAIL_UNAME")?;
-    let email_passwd = env::var("EMAIL_PASSWD")?;
     let email_addr = env::var("EMAIL_ADDRESS")?;
     let email_domain = env::var("EMAIL_DOMAIN")?;
     let root_email = env::var("MAIL_ROOT_ADDR")?;
@@ -365,218 +424,268 @@ pub fn check_expiry_dates(
 
     // Namespace — The namespace
     // Admins — Who owns and operates it
-    // Age — How many weeks old it is
+    // Age — How many weeks old it is, not counting paused time
+    // Raw Age — How many weeks old it is, counting everything
     // Action — What ShelfLife is going to do to it
-    report_table.add_row(row!["Namespace", "Admins", "Age", "Action"]);
+    // Signals — Every sign of life we know about, newest first
+    report_table.add_row(row!["Namespace", "Admins", "Age", "Raw Age", "Action", "Signals"]);
 
     let addr: &str = &*email_addr;
-    let mut mailer = SmtpClient::new_simple(&email_srv).unwrap()
-        .credentials(Credentials::new(email_uname.to_string(), email_passwd.to_string()))
-        .smtp_utf8(true)
-        .authentication_mechanism(Mechanism::Plain)
-        .connection_reuse(ConnectionReuseParameters::ReuseUnlimited).transport();
+    let mut mailer = get_mailer()?;
+
+    // Finals week, winter break, migration freezes...
+    let blackout = config.blackouts.iter().find(|window| window.contains(Utc::now()));
+    if let Some(window) = blackout {
+        println!("We are in the {} blackout. Spin-downs and archives are deferred until {}.", &window.name, &window.end);
+        warn!("In the {} blackout. Spin-downs and archives are deferred until {}.", &window.name, &window.end);
+    }
 
     let namespaces: Vec<DBItem> = get_db(mongo_client, collection).unwrap();
     for item in namespaces.iter(){
-        // Compare last update and discovery date and see which one is more recent and go off of that.
-        let last_update = DateTime::parse_from_rfc2822(&item.last_update).unwrap();
-        let age = match DateTime::parse_from_rfc2822(&item.discovery_date) {
-            Ok(date) => {
-                let discovery_date = date;
-                let since = match last_update.signed_duration_since(discovery_date) {
-                    d if d > Duration::nanoseconds(0) => Utc::now().signed_duration_since(last_update),
-                    _ => Utc::now().signed_duration_since(discovery_date),
-                };
-                since
-            },
-            _ => {
-                Utc::now().signed_duration_since(last_update)
-            }
-        };
-        
         print!("Checking status of {}...", &item.name);
         info!("Checking status of {}...", &item.name);
 
-        // TWENTY FOUR WEEKS!
-        if age > chrono::Duration::weeks(24) { // Check longest first, decending.
-            println!("The last update to {} was more than 24 weeks ago.", &item.name);
-            warn!("Age >24 weeks.");
+        // Projects can be up to date and still not be doing anything.
+        let idle = check_idle(client, config, item);
+        let flag = if idle { " [Idle]" } else { "" };
+
+        // Work out what the policy says, then act on it.
+        let evaluation = evaluate(item, config, idle, blackout);
+        let (raw_age, age) = (evaluation.raw_age, evaluation.age);
+        let policy = &evaluation.policy;
+        match &evaluation.verdict {
+            Verdict::Protected(rule) => {
+                // Never, ever touch a protected namespace, however it got in here.
+                println!(" PROTECTED ({})! Refusing to touch it. Remove it from the {} table!", rule, collection);
+                error!("REFUSING TO TOUCH PROTECTED NAMESPACE {} ({}). It should not be in the {} table!", &item.name, rule, collection);
+                continue;
+            },
+            Verdict::Skipped(why) => {
+                println!(" {}.", why);
+                info!("{} {}.", &item.name, why);
+                continue;
+            },
+            _ => {},
+        }
+
+        // Ephemeral projects get a countdown, once per configured notice.
+        if let (Some(ttl), Some((key, days_left))) = (evaluation.ttl, &evaluation.ttl_notice) {
+            let days_left = *days_left;
+            println!(" {} days left until its TTL ({}).", days_left, ttl.to_rfc2822());
+            info!("{} days left until the TTL of {}.", days_left, &item.name);
             if report {
                 report_table.add_row(row![
                     &item.name,
                     format!("{:?}", item.admins),
                     Duration::num_weeks(&age),
-                    "Archive"]);
+                    Duration::num_weeks(&raw_age),
+                    format!("TTL countdown ({} days left){}", days_left, flag),
+                    fmt_signals(item)]);
             }
-            if !dryrun {
+            if usemail {
+                let subject = policy.render_ttl(&policy.ttl_subject, item, ttl, days_left);
+                let body = policy.render_ttl(&policy.ttl_template, item, ttl, days_left);
+                if notify_admins(&mut mailer, addr, &email_domain, send_to_root, item, &subject, &body) {
+                    record_db_item_notice(mongo_client, collection, &item.name, key, &Utc::now().to_rfc2822())?;
+                }
+            }
+        }
+
+        let index = match evaluation.verdict {
+            Verdict::Ok => {
+                println!(" ok.");
+                if idle && report {
+                    report_table.add_row(row![
+                        &item.name,
+                        format!("{:?}", item.admins),
+                        Duration::num_weeks(&age),
+                        Duration::num_weeks(&raw_age),
+                        "Idle",
+                        fmt_signals(item)]);
+                }
+                continue;
+            },
+            Verdict::Holding(ref reason) => {
+                println!(" already at {} since {} ({}).", &item.stage, &item.stage_entered, reason);
+                if report {
+                    report_table.add_row(row![
+                        &item.name,
+                        format!("{:?}", item.admins),
+                        Duration::num_weeks(&age),
+                        Duration::num_weeks(&raw_age),
+                        format!("Holding at {}: {}{}", &item.stage, reason, flag),
+                        fmt_signals(item)]);
+                }
+                continue;
+            },
+            Verdict::Reset => {
+                // Something reset the project's clock. Drop it back down quietly.
+                let stage_name = evaluation.target.map_or("", |target| policy.stages[target].name.as_str());
+                println!(" back to {}.", if stage_name == "" { "ok" } else { stage_name });
+                info!("{} moved back to stage \"{}\".", &item.name, stage_name);
+                if !dryrun {
+                    let entered = if stage_name == "" { String::new() } else { Utc::now().to_rfc2822() };
+                    set_db_item_stage(mongo_client, collection, &item.name, stage_name, &entered)?;
+                    // Its admins have to be warned all over again next time around.
+                    clear_db_item_notices(mongo_client, collection, item)?;
+                }
+                continue;
+            },
+            Verdict::Deferred(index, _) | Verdict::Blocked(index, _) | Verdict::Advance(index) => index,
+            Verdict::Protected(_) | Verdict::Skipped(_) => continue,
+        };
+        let stage = &policy.stages[index];
+        let target = evaluation.target.unwrap();
+
+        if evaluation.ttl_expired {
+            println!("The TTL of {} has run out.", &item.name);
+            warn!("TTL ran out. Stage: {}", stage.name);
+        } else if stage.idle_only {
+            println!("{} has been idle, and its last update was more than {} days ago.", &item.name, stage.after_days);
+            warn!("Idle, age >{} days. Stage: {}", stage.after_days, stage.name);
+        } else {
+            println!("The last update to {} was more than {} days ago.", &item.name, stage.after_days);
+            warn!("Age >{} days. Stage: {}", stage.after_days, stage.name);
+        }
+        match &evaluation.verdict {
+            Verdict::Deferred(_, window) => {
+                println!("Deferring {} for {} because of the {} blackout.", &stage.name, &item.name, window);
+                info!("Deferring {} for {} because of the {} blackout.", &stage.name, &item.name, window);
+                if report {
+                    report_table.add_row(row![
+                        &item.name,
+                        format!("{:?}", item.admins),
+                        Duration::num_weeks(&age),
+                        Duration::num_weeks(&raw_age),
+                        format!("{} (deferred: {} blackout){}", &stage.name, window, flag),
+                        fmt_signals(item)]);
+                }
+                continue;
+            },
+            Verdict::Blocked(_, reason) => {
+                println!("{} hasn't had {} days of notice ({}). Not archiving.", &item.name, policy.minimum_notice_days, reason);
+                warn!("{} hasn't had {} days of notice ({}). Not archiving.", &item.name, policy.minimum_notice_days, reason);
+                if report {
+                    report_table.add_row(row![
+                        &item.name,
+                        format!("{:?}", item.admins),
+                        Duration::num_weeks(&age),
+                        Duration::num_weeks(&raw_age),
+                        format!("Blocked: {}{}", reason, flag),
+                        fmt_signals(item)]);
+                }
+                continue;
+            },
+            _ => {},
+        }
+
+        if report {
+            let action = match index < target {
+                true => format!("{} ({} pending){}", &stage.name, &policy.stages[target].name, flag),
+                false => format!("{}{}", &stage.name, flag),
+            };
+            report_table.add_row(row![
+                &item.name,
+                format!("{:?}", item.admins),
+                Duration::num_weeks(&age),
+                Duration::num_weeks(&raw_age),
+                action,
+                fmt_signals(item)]);
+        }
+        if dryrun {
+            continue;
+        }
+
+        match stage.action {
+            Action::Archive => {
                 println!("Project marked for deletion...");
                 println!("Exporting project...");
-                let export_result = export_project(&item.name);
+                let export_result = export_project(client, &item.name);
                 match export_result {
                     Ok(()) => {
                         println!("Export complete.");
                         info!("Exported.")
                     }
-                    _ => {
-                        println!("Export failed!");
-                        error!("Export failed!");
-                        dbg!(&export_result);
+                    Err(e) => {
+                        // No backup, no delete. Try again next run.
+                        println!("Export failed! Not deleting {}.", &item.name);
+                        error!("Export of {} failed, so it wasn't deleted: {}", &item.name, e);
+                        continue;
                     }
                 }
                 println!("Requesting API to delete...");
 
-                let delete_call = format!("https://{}/apis/project.openshift.io/v1/projects/{}", endpoint, &item.name);
-                let _result = delete_call_api(&http_client, &delete_call);
-                let _db_result = remove_db_item(mongo_client, collection, &item.name);
+                // Keep tracking it until it's really gone, so the next run tries again.
+                if let Err(e) = client.delete_project(&item.name) {
+                    println!("Could not delete {}. Trying again next run.", &item.name);
+                    error!("Could not delete {}: {}", &item.name, e);
+                    continue;
+                }
+                remove_db_item(mongo_client, collection, &item.name)?;
 
                 println!("Project has been marked for deletion and removed from ShelfLife DB.");
                 info!("Marked for deletion.");
-
-                // Find the names of the admins and send them M A I L!
-                if usemail {
-                    println!("Notifying admins...");
-                    for name in item.admins.iter() {
-                        let strpname = name.replace("\"", "");
-                        if !send_to_root && &strpname == "root" {
-                            println!("I am NOT going to email root.");
-                        } else {
-                            println!("Notifying {}", &strpname);
-                            info!("Notifying {}", &strpname);
-                            let strpname = name.replace("\"", "");
-                            let email = Email::builder()
-                                .to((format!("{}@{}", strpname, email_domain), strpname))
-                                .from(addr)
-                                .subject("Hi, I nuked your project :)")
-                                .text(format!("Hello! You are receiving this message because your OKD project, {}, has now gone more than 24 weeks without an update ({}). It has been deleted from OKD. You can find a backup of the project in your homedir at <link>. Thank you for using ShelfLife, try not to let your pods get too moldy next time.", &item.name, &item.last_update))
-                                .build();
-                            match email {
-                                Err(e) => {
-                                    println!("Could not send email. Invalid email address?");
-                                    error!("Could not send email.");
-                                    eprintln!("{}", e);
-                                },
-                                _ => {
-                                    let _mail_result = mailer.send(email.unwrap().into());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-
-        }else if age > chrono::Duration::weeks(16) {
-            println!("The last update to {} was more than 16 weeks ago.", &item.name);
-            warn!("Age >16 weeks.");
-            if report {
-                report_table.add_row(row![
-                    &item.name,
-                    format!("{:?}", item.admins),
-                    Duration::num_weeks(&age),
-                    "Spin-Down"]);
-            }
-            if !dryrun {
+            },
+            Action::SpinDown => {
                 println!("Spinning down...");
                 info!("Spinning down...");
 
                 // Query deployment configs that will need to be spun down.
-                let deploycfgs_call = format!(
-                    "https://{}/apis/apps.openshift.io/v1/namespaces/{}/deploymentconfigs",
-                    endpoint, &item.name
-                );
-
-                let deploycfgs_resp = get_call_api(&http_client, &deploycfgs_call); // Make the call
-                let deploycfgs_json: DeploymentResponse = deploycfgs_resp?.json()?;
-                let mut deploys = Vec::new();
-                for item in deploycfgs_json.items {
-                    if item.status.replicas > 0 {
-                        println!("Spinning down {} replicas in {}", &item.status.replicas, &item.metadata.name);
-                        deploys.push(item.metadata.name);
-                    }
-                }
+                let deploycfgs_json = match client.deployment_configs(&item.name) {
+                    Ok(deploycfgs_json) => deploycfgs_json,
+                    Err(e) => {
+                        println!("Could not list the deploymentconfigs in {}. Trying again next run.", &item.name);
+                        error!("Could not list the deploymentconfigs in {}: {}", &item.name, e);
+                        continue;
+                    },
+                };
 
-                // Tell deploymentconfigs to scale down to 0 pods.
-                for deployment in deploys {
-                    let call = format!(
-                        "https://{}/oapi/v1/namespaces/{}/deploymentconfigs/{}/scale",
-                        endpoint, &item.name, &deployment
-                    );
-                    let post = format!(
-                    "{{\"apiVersion\":\"extensions/v1beta1\",\"kind\":\"Scale\",\"metadata\":{{\"name\":\"{}\",\"namespace\":\"{}\"}},\"spec\":{{\"replicas\":0}}}}",
-                    &deployment, &item.name);
-                    let _result = put_call_api(&http_client, &call, String::from(&post))?;
-                }
-                
-                if usemail {  
-                    // Find the names of the admins and send them M A I L!
-                    println!("Notifying admins...");
-                    for name in item.admins.iter() {
-                        let strpname = name.replace("\"", "");
-                        if !send_to_root && &strpname == "root" {
-                            println!("I am NOT going to email root.");
-                        } else {
-                            println!("Notifying {}", &strpname);
-                            info!("Notifying {}", &strpname);
-                            let email = Email::builder()
-                                .to((format!("{}@{}", strpname, email_domain), strpname))
-                                .from(addr)
-                                .subject("Your project's resources have been revoked.")
-                                .text(format!("Hello! You are receiving this message because your OKD project, {}, has now gone more than 16 weeks without an update ({}). All applications on the project have now been reduced to 0 pods. If you would like to revive it, do so, and its ShelfLife will reset. Otherwise, it will be deleted in another 8 weeks.", &item.name, &item.last_update))
-                                .build();
-                            match email {
-                                Err(e) => {
-                                    println!("Could not send email. Invalid email address?");
-                                    error!("Could not send email.");
-                                    eprintln!("{}", e);
-                                },
-                                _ => {
-                                    let _mail_result = mailer.send(email.unwrap().into());
-                                }
-                            }
-                        }
+                // Tell deploymentconfigs to scale down to 0 pods. Remember each one
+                // as soon as it's down, so we can tell if somebody brings it back,
+                // even if a later one fails. Keep any from a run that failed partway.
+                let mut deploys = item.spun_down.clone();
+                let mut failed = false;
+                for deployment in deploycfgs_json.items {
+                    if deployment.status.replicas == 0 {
+                        continue;
                     }
-                }
-            }
-        }else if age > chrono::Duration::weeks(12) {
-            println!("The last update to {} was more than 12 weeks ago.", &item.name);
-            warn!("Age >12 weeks.");
-            if report {
-                report_table.add_row(row![
-                    &item.name,
-                    format!("{:?}", item.admins),
-                    Duration::num_weeks(&age),
-                    "Nudge"]);
-            }
-            if !dryrun && usemail {
-                // Find the names of the admins and send them M A I L!
-                println!("Notifying admins...");
-                for name in item.admins.iter() {
-                    let strpname = name.replace("\"", "");
-                    if !send_to_root && &strpname == "root" {
-                        println!("I am NOT going to email root.");
-                    } else {
-                        println!("Notifying {}", &strpname);
-                        info!("Notifying {}", &strpname);
-                        let email = Email::builder()
-                            .to((format!("{}@{}", strpname, email_domain), strpname))
-                            .from(addr)
-                            .subject(format!("Old OKD project: {}", &item.name))
-                            .text(format!("Hello! You are receiving this message because your OKD project, {}, has gone more than 12 weeks without an update ({}). Please consider updating with a build, deployment, or asking an RTP to have ShelfLife ignore it. Thanks!.", &item.name, &item.last_update))
-                            .build();
-                        match email {
-                            Err(e) => {
-                                println!("Could not send email. Invalid email address?");
-                                error!("Could not send email.");
-                                eprintln!("{}", e);
-                            },
-                            _ => {
-                                let _mail_result = mailer.send(email.unwrap().into());
-                            }
-                        }
+                    println!("Spinning down {} replicas in {}", &deployment.status.replicas, &deployment.metadata.name);
+                    if let Err(e) = client.scale(&item.name, &deployment.metadata.name, 0) {
+                        error!("Could not spin down {} in {}: {}", &deployment.metadata.name, &item.name, e);
+                        failed = true;
+                        break;
                     }
+                    deploys.retain(|spun_down| spun_down.name != deployment.metadata.name);
+                    deploys.push(SpunDown { name: deployment.metadata.name, replicas: deployment.status.replicas as i64 });
+                    set_db_item_spun_down(mongo_client, collection, &item.name, &deploys)?;
+                }
+                if failed {
+                    // Leave the stage alone, so the next run finishes the job.
+                    println!("Could not finish spinning down {}. Trying again next run.", &item.name);
+                    continue;
                 }
+            },
+            Action::Nudge => {},
+        }
+
+        // Only move the project along once its admins have the notice, so a
+        // mail outage can't skip anybody's warning. The stage's action is
+        // safe to run again next time.
+        if usemail {
+            let subject = policy.render(&stage.subject, index, item, age);
+            let body = policy.render(&stage.template, index, item, age);
+            if !notify_admins(&mut mailer, addr, &email_domain, send_to_root, item, &subject, &body) {
+                println!("Nobody got the {} notice for {}! Trying again next run.", &stage.name, &item.name);
+                error!("Nobody got the {} notice for {}! Leaving it at stage \"{}\".", &stage.name, &item.name, &item.stage);
+                continue;
+            }
+        }
+        if stage.action != Action::Archive {
+            set_db_item_stage(mongo_client, collection, &item.name, &stage.name, &Utc::now().to_rfc2822())?;
+            if usemail {
+                // Keep the receipt, so we know they were warned before we delete anything.
+                record_db_item_notice(mongo_client, collection, &item.name, &stage.name, &Utc::now().to_rfc2822())?;
             }
-        } else {
-            println!(" ok.");
         }
     }
     if report {
@@ -584,13 +693,17 @@ pub fn check_expiry_dates(
             true => "Hello! ShelfLife is going to take the following actions against these projects soon. If this doesn't look right, hop on a console and fix it!",
             false => "Hello! ShelfLife has just taken the following actions against these projects. If something doesn't look right, please direct a complaint to /dev/null on any user machine. Thank you for using ShelfLife! Get a job, or get D E L E T E D.", // TODO: Make these customizable with env variables. (crikey this is really just turning into some kind of config file now, innit?)
         };
+        let blackout_message = match blackout {
+            Some(window) => format!("\nThe {} blackout is in effect until {}, so spin-downs and archives have been deferred.\n", &window.name, &window.end),
+            None => String::new(),
+        };
         info!("Sending report...");
         println!("Sending report...");
         let email = Email::builder()
             .to((format!("{}@{}", root_email, email_domain), root_email))
             .from(addr)
             .subject(format!("ShelfLife Report"))
-            .text(format!("{} \n {}", report_message, report_table.to_string()))
+            .text(format!("{} \n{} {}", report_message, blackout_message, report_table.to_string()))
             .build();
         match email {
             Err(e) => {
@@ -599,7 +712,10 @@ pub fn check_expiry_dates(
                 eprintln!("{}", e);
             },
             _ => {
-                let _mail_result = mailer.send(email.unwrap().into());
+                if let Err(e) = mailer.send(email.unwrap().into()) {
+                    println!("Could not send report.");
+                    error!("Could not send report: {}", e);
+                }
             }
         }
     }
@@ -610,94 +726,507 @@ pub fn check_expiry_dates(
     Ok(())
 }
 
-pub fn export_project(project: &str) -> Result<()> {
-    let token = env::var("OKD_TOKEN")?;
-    let endpoint = env::var("ENDPOINT")?;
+// What the policy says should happen to a project.
+pub enum Verdict {
+    Protected(String),       // The rule that protects it.
+    Skipped(String),         // Snoozed or exempt, and until when.
+    Ok,                      // Not in any stage, and not due for one.
+    Holding(String),         // Already in the stage its age calls for, and why it isn't moving on.
+    Reset,                   // Something happened. Back to the stage its age calls for.
+    Deferred(usize, String), // Due for a stage, but there's a blackout on.
+    Blocked(usize, String),  // Due to be archived, but its admins haven't had enough notice.
+    Advance(usize),          // Due for a stage.
+}
+
+// Everything check_expiry_dates works out about a project before it does
+// anything to it. --explain prints one of these.
+pub struct Evaluation {
+    pub raw_age: Duration,
+    pub age: Duration,
+    pub idle: bool,
+    pub policy: Policy,             // The policy, after any overrides.
+    pub target: Option<usize>,      // The furthest stage its age has reached.
+    pub current: Option<usize>,     // The stage it's in.
+    pub ttl: Option<DateTime<Utc>>,
+    pub ttl_expired: bool,
+    pub ttl_notice: Option<(String, i64)>, // A countdown notice that's due, and how many days are left.
+    pub verdict: Verdict,
+}
+
+// Runs the policy against a project. Has no side effects, so it's safe to
+// call for anything.
+pub fn evaluate(item: &DBItem, config: &Config, idle: bool, blackout: Option<&Window>) -> Evaluation {
+    // How long it's been since anything happened, minus any paused time.
+    let (raw_age, age) = get_age(item, config);
+
+    // Honor any overrides the project's admins have annotated it with.
+    let policy = match &item.overrides.expire_after {
+        Some(after) => match parse_duration(after) {
+            Ok(lifetime) => config.policy.with_lifetime(lifetime),
+            Err(e) => {
+                warn!("Invalid expire-after annotation on {}: {}", &item.name, e);
+                config.policy.clone()
+            },
+        },
+        None => config.policy.clone(),
+    };
+    let mut index = policy.stage_for_age(age, idle);
+    if let Some(stage_name) = &item.overrides.stage_override {
+        index = policy.cap_stage(index, stage_name);
+    }
+
+    // Ephemeral projects go away on their TTL, no matter how active they are.
+    let ttl = get_ttl(item);
+    let mut ttl_expired = false;
+    let mut ttl_notice = None;
+    if let Some(ttl) = ttl {
+        let days_left = ttl.signed_duration_since(Utc::now()).num_days();
+        if Utc::now() >= ttl {
+            ttl_expired = true;
+            index = policy.ttl_stage();
+        } else if let Some(days) = policy.ttl_countdown(days_left) {
+            let key = format!("TTL {}d", days);
+            if !item.notices.contains_key(&key) {
+                ttl_notice = Some((key, days_left));
+            }
+        }
+    }
+
+    // Compare the stage the project is in with the furthest stage its age
+    // has reached. Each stage's action only ever happens once.
+    let target = index;
+    let current = policy.stages.iter().position(|stage| stage.name == item.stage);
+    if current.is_none() && item.stage != "" {
+        warn!("{} is in stage {}, which isn't in the policy anymore. Starting over.", &item.name, &item.stage);
+    }
+
+    let verdict = evaluate_verdict(item, config, &policy, idle, blackout, target, current, ttl_expired);
+    Evaluation { raw_age, age, idle, policy, target, current, ttl, ttl_expired, ttl_notice, verdict }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn evaluate_verdict(
+    item: &DBItem,
+    config: &Config,
+    policy: &Policy,
+    idle: bool,
+    blackout: Option<&Window>,
+    target: Option<usize>,
+    current: Option<usize>,
+    ttl_expired: bool,
+) -> Verdict {
+    // Never, ever touch a protected namespace, however it got in here.
+    if let Some(rule) = config.protected.matches(item) {
+        return Verdict::Protected(rule);
+    }
+
+    // Leave snoozed projects alone until their extension runs out.
+    if let Some(snooze) = &item.snooze {
+        match parse_date(&snooze.until) {
+            Ok(until) if Utc::now() < until => {
+                return Verdict::Skipped(format!("snoozed until {} by {} ({})", &snooze.until, &snooze.granted_by, &snooze.reason));
+            },
+            Ok(_) => {},
+            Err(e) => warn!("Invalid snooze date on {}: {}", &item.name, e),
+        }
+    }
+    if let Some(until) = &item.overrides.exempt_until {
+        match parse_date(until) {
+            Ok(date) if Utc::now() < date => return Verdict::Skipped(format!("exempt until {}", until)),
+            Ok(_) => {},
+            Err(e) => warn!("Invalid exempt-until annotation on {}: {}", &item.name, e),
+        }
+    }
+
+    if target <= current {
+        return match (target, current) {
+            (None, _) if item.stage == "" => Verdict::Ok,
+            (Some(_), _) if target == current => Verdict::Holding(holding_reason(item, policy, current, idle)),
+            _ => Verdict::Reset,
+        };
+    }
+
+    // Move at most one stage per run, so a project is never surprised
+    // by a stage it didn't get warned about. Never past the target, either,
+    // since that's where a stage-override caps it.
+    let mut index = match policy.next_stage(current, idle) {
+        Some(index) if Some(index) <= target => index,
+        // Everything up to the target only applies to idle projects.
+        _ if item.stage == "" => return Verdict::Ok,
+        _ => return Verdict::Holding(holding_reason(item, policy, current, idle)),
+    };
+    if ttl_expired {
+        // The countdown was the nudge. Go straight to the destructive stages.
+        if let Some(first) = policy.stages.iter().position(|stage| stage.action != Action::Nudge && (idle || !stage.idle_only)) {
+            if Some(first) <= target {
+                index = index.max(first);
+            }
+        }
+    }
+    let stage = &policy.stages[index];
+
+    // Treat destructive stages like a dryrun during a blackout. Don't
+    // advance the project's stage either, so it picks up where it was after.
+    if let (Some(window), true) = (blackout, stage.action != Action::Nudge) {
+        return Verdict::Deferred(index, window.name.to_string());
+    }
+
+    // Don't delete anything until its admins have had fair warning.
+    if stage.action == Action::Archive {
+        let warned = policy.first_warning(index, item, ttl_expired);
+        let notice = Duration::days(policy.minimum_notice_days);
+        let enough_notice = match warned {
+            Some(date) => Utc::now().signed_duration_since(date) >= notice,
+            None => false,
+        };
+        if !enough_notice {
+            let reason = match warned {
+                Some(date) => format!("warned {}", date.to_rfc2822()),
+                None => "never warned".to_string(),
+            };
+            return Verdict::Blocked(index, reason);
+        }
+    }
+    Verdict::Advance(index)
+}
+
+// Why a project is staying in its stage for now. Shows up in the cull report.
+fn holding_reason(item: &DBItem, policy: &Policy, current: Option<usize>, idle: bool) -> String {
+    let mut reason = match policy.next_stage(current, idle) {
+        None if idle => "no stage after it".to_string(),
+        None => "the stages after it are only for idle projects".to_string(),
+        Some(next) => match &item.overrides.stage_override {
+            Some(stage_name) if policy.cap_stage(Some(next), stage_name) != Some(next) => {
+                format!("stage-override {} keeps it out of {}", stage_name, &policy.stages[next].name)
+            },
+            _ => format!("{} comes after {} days", &policy.stages[next].name, policy.stages[next].after_days),
+        },
+    };
+    if !item.notices.contains_key(&item.stage) {
+        reason.push_str(&format!(", and its {} notice isn't on record", &item.stage));
+    }
+    reason
+}
+
+// Asks Prometheus if a project is idle, if it's been set up to.
+fn check_idle(client: &OkdClient, config: &Config, item: &DBItem) -> bool {
+    if !config.usage.enabled() {
+        return false;
+    }
+    match config.usage.usage(client, &item.name) {
+        Ok(usage) => {
+            let idle = config.usage.is_idle(&usage);
+            info!("{} used {:.3} cores and {:.0} B/s of network.", &item.name, usage.cpu_cores, usage.network_bytes_per_second);
+            if idle {
+                print!(" idle...");
+            }
+            idle
+        },
+        Err(e) => {
+            // Don't hold it against the project if we can't tell.
+            warn!("Could not check the usage of {}: {}", &item.name, e);
+            false
+        },
+    }
+}
+
+// Walks through how check_expiry_dates would handle one namespace, without
+// doing any of it.
+pub fn explain_namespace(
+    client: &OkdClient,
+    mongo_client: &mongodb::Client,
+    config: &Config,
+    collection: &str,
+    namespace: &str,
+) -> Result<()> {
+    let item = get_db(mongo_client, collection)?
+        .into_iter()
+        .find(|item| item.name == namespace)
+        .ok_or_else(|| format!("{} is not in the {} table.", namespace, collection))?;
+    let blackout = config.blackouts.iter().find(|window| window.contains(Utc::now()));
+    print!("Explaining {}...", namespace);
+    let idle = check_idle(client, config, &item);
+    println!(" (nothing will be changed. Run -k first to refresh what the API knows.)\n");
+    let evaluation = evaluate(&item, config, idle, blackout);
+    let policy = &evaluation.policy;
+
+    println!("Signals:");
+    for line in fmt_signals(&item).lines() {
+        println!("    {}", line);
+    }
+    println!("Discovered:     {}", &item.discovery_date);
+    println!("Last update:    {} ({})", &item.last_update, &item.cause);
+    let (since, won) = get_since(&item, config);
+    println!("Counting from:  {} (the {})", since.to_rfc2822(), won);
+    println!("Raw age:        {} days ({} weeks)", evaluation.raw_age.num_days(), evaluation.raw_age.num_weeks());
+    println!("Effective age:  {} days ({} weeks), not counting pauses", evaluation.age.num_days(), evaluation.age.num_weeks());
+    if config.usage.enabled() {
+        println!("Idle:           {}", if evaluation.idle { "yes" } else { "no" });
+    }
+    let overrides = vec![
+        ("expire-after", &item.overrides.expire_after),
+        ("exempt-until", &item.overrides.exempt_until),
+        ("stage-override", &item.overrides.stage_override),
+        ("ttl", &item.overrides.ttl),
+    ];
+    for (annotation, value) in overrides {
+        if let Some(value) = value {
+            println!("Override:       shelflife/{} = {}", annotation, value);
+        }
+    }
+    if let Some(ttl) = evaluation.ttl {
+        println!("TTL:            {}{}", ttl.to_rfc2822(), if evaluation.ttl_expired { " (ran out)" } else { "" });
+    }
+    match evaluation.target {
+        Some(target) => println!("Policy stage:   {} (after {} days)", &policy.stages[target].name, policy.stages[target].after_days),
+        None => println!("Policy stage:   none"),
+    }
+    match evaluation.current {
+        Some(current) => println!("Current stage:  {} since {}", &policy.stages[current].name, &item.stage_entered),
+        None => println!("Current stage:  none"),
+    }
+
+    // The stage after the one it'll be in, and when its age gets there.
+    let upcoming = |after: Option<usize>| -> String {
+        let next = match policy.next_stage(after, evaluation.idle) {
+            Some(next) => next,
+            None => return "nothing. It's in the last stage.".to_string(),
+        };
+        if let Some(stage_name) = &item.overrides.stage_override {
+            if policy.cap_stage(Some(next), stage_name) != Some(next) {
+                return format!("nothing. The stage-override keeps it out of {}.", &policy.stages[next].name);
+            }
+        }
+        let stage = &policy.stages[next];
+        let when = Utc::now() + (Duration::days(stage.after_days) - evaluation.age);
+        format!("{} ({:?}) around {}, if nothing happens before then.", &stage.name, stage.action, when.to_rfc2822())
+    };
+    let next_action = match &evaluation.verdict {
+        Verdict::Protected(rule) => format!("nothing, ever. It's protected ({}).", rule),
+        Verdict::Skipped(why) => format!("nothing. It's {}.", why),
+        Verdict::Ok => upcoming(None),
+        Verdict::Holding(_) => upcoming(evaluation.current),
+        Verdict::Reset => format!(
+            "back to {} on the next cull. Then {}",
+            evaluation.target.map_or("no stage", |target| policy.stages[target].name.as_str()),
+            upcoming(evaluation.target)
+        ),
+        Verdict::Deferred(index, window) => {
+            let end = config.blackouts.iter().find(|blackout| &blackout.name == window).map_or("", |blackout| blackout.end.as_str());
+            format!("{} ({:?}) once the {} blackout ends ({}).", &policy.stages[*index].name, policy.stages[*index].action, window, end)
+        },
+        Verdict::Blocked(index, reason) => {
+            let when = match policy.first_warning(*index, &item, evaluation.ttl_expired) {
+                Some(warned) => format!("around {}", (warned + Duration::days(policy.minimum_notice_days)).to_rfc2822()),
+                None => "once its admins have been warned".to_string(),
+            };
+            format!(
+                "{} ({:?}) {}. Blocked until its admins have had {} days of notice ({}).",
+                &policy.stages[*index].name, policy.stages[*index].action, when, policy.minimum_notice_days, reason
+            )
+        },
+        Verdict::Advance(index) => format!("{} ({:?}) on the next cull.", &policy.stages[*index].name, policy.stages[*index].action),
+    };
+    println!("Next action:    {}", next_action);
+    if let Some((_, days_left)) = evaluation.ttl_notice {
+        println!("The next cull also sends a TTL countdown notice ({} days left).", days_left);
+    }
+    Ok(())
+}
+
+// Sets up the SMTP connection all of ShelfLife's mail goes through.
+fn get_mailer() -> Result<SmtpTransport> {
+    let email_srv = env::var("EMAIL_SRV")?;
+    let email_uname = env::var("EMAIL_UNAME")?;
+    let email_passwd = env::var("EMAIL_PASSWD")?;
+    let mailer = SmtpClient::new_simple(&email_srv)?
+        .credentials(Credentials::new(email_uname.to_string(), email_passwd.to_string()))
+        .smtp_utf8(true)
+        .authentication_mechanism(Mechanism::Plain)
+        .connection_reuse(ConnectionReuseParameters::ReuseUnlimited).transport();
+    Ok(mailer)
+}
+
+// Find the names of the admins and send them M A I L!
+// Returns true if at least one of them actually got it.
+fn notify_admins(
+    mailer: &mut SmtpTransport,
+    addr: &str,
+    email_domain: &str,
+    send_to_root: bool,
+    item: &DBItem,
+    subject: &str,
+    body: &str,
+) -> bool {
+    println!("Notifying admins...");
+    let mut delivered = false;
+    for name in item.admins.iter() {
+        let strpname = name.replace("\"", "");
+        if !send_to_root && &strpname == "root" {
+            println!("I am NOT going to email root.");
+        } else {
+            println!("Notifying {}", &strpname);
+            info!("Notifying {}", &strpname);
+            let email = Email::builder()
+                .to((format!("{}@{}", strpname, email_domain), strpname.to_string()))
+                .from(addr)
+                .subject(subject)
+                .text(body)
+                .build();
+            match email {
+                Err(e) => {
+                    println!("Could not send email. Invalid email address?");
+                    error!("Could not send email.");
+                    eprintln!("{}", e);
+                },
+                _ => {
+                    match mailer.send(email.unwrap().into()) {
+                        Ok(_) => delivered = true,
+                        Err(e) => {
+                            println!("Could not send email to {}.", &strpname);
+                            error!("Could not send email to {}: {}", &strpname, e);
+                        },
+                    }
+                }
+            }
+        }
+    }
+    delivered
+}
+
+pub fn export_project(client: &OkdClient, project: &str) -> Result<()> {
     let fail = "failed to execute process";
     let path = env::var("BACKUP_PATH")?; // One should hope this is somewhere they have write access to.
 
     // Export project
     Command::new("sh").arg("-c").arg(format!("mkdir {}", &path))
     .current_dir("/").status().expect(fail);
-    Command::new("sh").arg("-c").arg(format!("oc login https://{} --token={}", endpoint, token))
-    .current_dir(&path).status().expect(fail);
+    // oc logs in however we did, instead of whatever its own kubeconfig says.
+    let kubeconfig = client.write_kubeconfig()?;
+    let result = export_with(&kubeconfig, &path, project);
+    let _ = fs::remove_file(&kubeconfig);
+    result
+}
+
+fn export_with(kubeconfig: &Path, path: &str, project: &str) -> Result<()> {
+    let fail = "failed to execute process";
+    let oc = |args: String| {
+        Command::new("sh").arg("-c").arg(format!("oc {}", args))
+        .env("KUBECONFIG", kubeconfig)
+        .current_dir(&path).status().expect(fail)
+        .success()
+    };
     Command::new("sh").arg("-c").arg(format!("mkdir {}/{}", &path, project))
     .current_dir(&path).output().expect(fail);
-    Command::new("sh").arg("-c").arg(format!("oc project {}", project))
-    .current_dir(&path).output().expect(fail);
-    Command::new("sh").arg("-c").arg(format!("oc get -o yaml --export all > {}/project.yaml", project))
-    .current_dir(&path).output().expect(fail);
+    if !oc(format!("get -n {} -o yaml --export all > {}/project.yaml", project, project)) {
+        return Err(From::from(format!("Error: Could not export {}.", project)));
+    }
     println!("Done with GET for export all");
     let items = vec!["rolebindings", "serviceaccounts", "secrets", "imagestreamtags", "podpreset", "cms", "egressnetworkpolicies", "rolebindingrestrictions", "limitranges", "resourcequotas", "pvcs", "templates", "cronjobs", "statefulsets", "hpas", "deployments", "replicasets", "poddisruptionbudget", "endpoints"];
     for object in items {
-        Command::new("sh").arg("-c").arg(format!("oc get -o yaml --export {} > {}/{}.yaml", object, project, object))
-        .current_dir(&path).output().expect(fail);
-        println!("Done with GET for export {}", object);
+        // Not every cluster has every one of these, so don't give up over one.
+        if oc(format!("get -n {} -o yaml --export {} > {}/{}.yaml", project, object, project, object)) {
+            println!("Done with GET for export {}", object);
+        } else {
+            warn!("Could not export {} from {}.", object, project);
+        }
     }
 
     //Compress it
-    Command::new("sh").arg("-c").arg(format!("zip -r {}.zip {}", project, project))
-    .current_dir(&path).output().expect(fail); 
+    let zipped = Command::new("sh").arg("-c").arg(format!("zip -r {}.zip {}", project, project))
+    .current_dir(&path).status().expect(fail);
+    if !zipped.success() {
+        return Err(From::from(format!("Error: Could not zip up the export of {}.", project)));
+    }
     Ok(())
 }
 
-/*                                       API FUNCTIONS  */
-/*  --------------------------------------------------  */
+// When a project is due to be deleted no matter what. Whichever comes first of
+// the shelflife/ttl annotation and a TTL set from the command line.
+fn get_ttl(item: &DBItem) -> Option<DateTime<Utc>> {
+    vec![&item.ttl, &item.overrides.ttl]
+        .into_iter()
+        .filter_map(|ttl| ttl.as_ref())
+        .filter_map(|ttl| match parse_date(ttl) {
+            Ok(date) => Some(date),
+            Err(e) => {
+                warn!("Invalid TTL on {}: {}", &item.name, e);
+                None
+            },
+        })
+        .min()
+}
 
-// Make a call to the Openshift API about some namespace info.
-pub fn get_call_api(http_client: &reqwest::Client, call: &str,) -> Result<reqwest::Response> {
-    let token = env::var("OKD_TOKEN")?;
-    let response = http_client 
-        .get(call)
-        .header("Authorization", format!("Bearer {}", token))
-        .send()?;
-
-    // Ensure the call was successful
-    if response.status() == StatusCode::OK {
-        Ok(response)
-    } else {
-        return Err(From::from(format!(
-            "Error! Could not run API call. Call: {}, Code: {}", call, response.status()),
-        ));
-    }
-}
-
-pub fn put_call_api(http_client: &reqwest::Client, call: &str, post: String,) -> Result<reqwest::Response> {
-    let token = env::var("OKD_TOKEN")?;
-    let response = http_client
-        .put(call)
-        .header("Authorization", format!("Bearer {}", token))
-        .body(post)
-        .send()?;
-     
-    // Ensure the call was successful
-    if response.status() == StatusCode::OK {
-        Ok(response)
-    } else {
-        error!("Could not run API call. Call: {}, Code: {}", call, response.status());
-        return Err(From::from(format!(
-            "Error: Could not run API call. Call: {}, Code: {}", call, response.status()),
-        ));
-    }
-}
-
-pub fn delete_call_api(http_client: &reqwest::Client, call: &str,) -> Result<reqwest::Response> {
-    let token = env::var("OKD_TOKEN")?;
-    let response = http_client
-        .delete(call)
-        .header("Authorization", format!("Bearer {}", token))
-        .send()?;
-     
-    // Ensure the call was successful
-    if response.status() == StatusCode::OK {
-        Ok(response)
-    } else {
-        error!("Could not run API call. Call: {}, Code: {}", call, response.status());
-        return Err(From::from(format!(
-            "Error: Could not run API call. Call: {}, Code: {}", call, response.status()),
-        ));
+// Every signal we have for a project, one per line, newest first.
+fn fmt_signals(item: &DBItem) -> String {
+    let mut signals: Vec<(&String, DateTime<FixedOffset>)> = item.signals
+        .iter()
+        .filter_map(|(source, found)| DateTime::parse_from_rfc2822(found).ok().map(|found| (source, found)))
+        .collect();
+    signals.sort_by_key(|&(_, found)| std::cmp::Reverse(found));
+    signals
+        .iter()
+        .map(|(source, found)| format!("{}: {}", source, found.format("%d %b %Y")))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Whichever is more recent of a project's last sign of life and its discovery
+// date, and which one it was. The signals get another look with today's
+// activity config, so a build that's gotten too old to count stops counting
+// whether or not anybody has synced since. Projects synced before there were
+// signals go off of their last update.
+fn get_since(item: &DBItem, config: &Config) -> (DateTime<Utc>, String) {
+    let (last_update, won) = match config.activity.last_active(&item.signals, Utc::now()) {
+        Some((source, found)) => (found.with_timezone(&Utc), format!("{} signal", source)),
+        None => (
+            DateTime::parse_from_rfc2822(&item.last_update).unwrap().with_timezone(&Utc),
+            "last update".to_string(),
+        ),
+    };
+    match DateTime::parse_from_rfc2822(&item.discovery_date) {
+        Ok(discovery_date) if discovery_date > last_update => (discovery_date.with_timezone(&Utc), "discovery date".to_string()),
+        _ => (last_update, won),
     }
+}
 
+// Works out how long it's been since a project was last updated (or
+// discovered, whichever is more recent). Returns the raw age, and the
+// effective age, which doesn't count time spent in paused periods or snoozed.
+fn get_age(item: &DBItem, config: &Config) -> (Duration, Duration) {
+    let now = Utc::now();
+    let (since, _) = get_since(item, config);
+    let raw_age = now.signed_duration_since(since);
+
+    // A snooze is just a pause for one project.
+    let snoozed = item.snooze.as_ref().and_then(|snooze| {
+        match (parse_date(&snooze.granted), parse_date(&snooze.until)) {
+            (Ok(start), Ok(end)) => Some((start, end)),
+            _ => None,
+        }
+    });
+
+    // Clip each pause to the time we're measuring, then merge any that
+    // overlap so nothing gets subtracted twice.
+    let mut paused: Vec<(DateTime<Utc>, DateTime<Utc>)> = config.pauses
+        .iter()
+        .filter_map(|window| window.bounds().ok())
+        .chain(snoozed)
+        .map(|(start, end)| (start.max(since), end.min(now)))
+        .filter(|(start, end)| start < end)
+        .collect();
+    paused.sort();
+    let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    for (start, end) in paused {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    let age = merged
+        .iter()
+        .fold(raw_age, |age, (start, end)| age - end.signed_duration_since(*start));
+    (raw_age, age)
 }
 
 /*                                  DATABASE FUNCTIONS  */
@@ -718,6 +1247,17 @@ fn get_db(mongo_client: &mongodb::Client, collection: &str) -> Result<Vec<DBItem
             let mut doc_discovery_date = String::new();
             let mut doc_last_deployment = String::new();
             let mut doc_cause = String::new();
+            let mut doc_overrides = Overrides::default();
+            let mut doc_stage = String::new();
+            let mut doc_stage_entered = String::new();
+            let mut doc_notices = HashMap::new();
+            let mut doc_snooze = None;
+            let mut doc_ignore = None;
+            let mut doc_ttl = None;
+            let mut doc_labels = HashMap::new();
+            let mut doc_heartbeat = None;
+            let mut doc_signals = HashMap::new();
+            let mut doc_spun_down = Vec::new();
             if let Some(&Bson::String(ref name)) = item.get("name") {
                 doc_name = name.to_string();
             }
@@ -736,12 +1276,96 @@ fn get_db(mongo_client: &mongodb::Client, collection: &str) -> Result<Vec<DBItem
             if let Some(&Bson::String(ref cause)) = item.get("cause") {
                 doc_cause = cause.to_string();
             }
+            if let Some(&Bson::String(ref expire_after)) = item.get("expire_after") {
+                doc_overrides.expire_after = Some(expire_after.to_string());
+            }
+            if let Some(&Bson::String(ref exempt_until)) = item.get("exempt_until") {
+                doc_overrides.exempt_until = Some(exempt_until.to_string());
+            }
+            if let Some(&Bson::String(ref stage_override)) = item.get("stage_override") {
+                doc_overrides.stage_override = Some(stage_override.to_string());
+            }
+            if let Some(&Bson::String(ref ttl)) = item.get("ttl_override") {
+                doc_overrides.ttl = Some(ttl.to_string());
+            }
+            if let Some(&Bson::String(ref stage)) = item.get("stage") {
+                doc_stage = stage.to_string();
+            }
+            if let Some(&Bson::String(ref stage_entered)) = item.get("stage_entered") {
+                doc_stage_entered = stage_entered.to_string();
+            }
+            if let Some(&Bson::Document(ref snooze)) = item.get("snooze") {
+                doc_snooze = Some(Snooze {
+                    granted: snooze.get_str("granted").unwrap_or_default().to_string(),
+                    until: snooze.get_str("until").unwrap_or_default().to_string(),
+                    reason: snooze.get_str("reason").unwrap_or_default().to_string(),
+                    granted_by: snooze.get_str("granted_by").unwrap_or_default().to_string(),
+                });
+            }
+            if let Some(&Bson::Document(ref ignore)) = item.get("ignore") {
+                doc_ignore = Some(Ignore {
+                    reason: ignore.get_str("reason").unwrap_or_default().to_string(),
+                    requested_by: ignore.get_str("requested_by").unwrap_or_default().to_string(),
+                    expires: ignore.get_str("expires").ok().map(|expires| expires.to_string()),
+                });
+            }
+            if let Some(&Bson::String(ref ttl)) = item.get("ttl") {
+                doc_ttl = Some(ttl.to_string());
+            }
+            if let Some(&Bson::Document(ref heartbeat)) = item.get("heartbeat") {
+                doc_heartbeat = Some(Heartbeat {
+                    at: heartbeat.get_str("at").unwrap_or_default().to_string(),
+                    by: heartbeat.get_str("by").unwrap_or_default().to_string(),
+                });
+            }
+            if let Some(&Bson::Document(ref labels)) = item.get("labels") {
+                for (key, value) in labels.iter() {
+                    if let Bson::String(ref value) = *value {
+                        doc_labels.insert(key.to_string(), value.to_string());
+                    }
+                }
+            }
+            if let Some(&Bson::Document(ref signals)) = item.get("signals") {
+                for (source, found) in signals.iter() {
+                    if let Bson::String(ref found) = *found {
+                        doc_signals.insert(source.to_string(), found.to_string());
+                    }
+                }
+            }
+            if let Some(&Bson::Array(ref spun_down)) = item.get("spun_down") {
+                for deployment in spun_down.iter() {
+                    if let Bson::Document(ref deployment) = *deployment {
+                        doc_spun_down.push(SpunDown {
+                            name: deployment.get_str("name").unwrap_or_default().to_string(),
+                            replicas: deployment.get_i64("replicas").unwrap_or_default(),
+                        });
+                    }
+                }
+            }
+            if let Some(&Bson::Document(ref notices)) = item.get("notices") {
+                for (stage, delivered) in notices.iter() {
+                    if let Bson::String(ref delivered) = *delivered {
+                        doc_notices.insert(stage.to_string(), delivered.to_string());
+                    }
+                }
+            }
             let namespace_document = DBItem {
                 name: doc_name.as_str().to_string(),
                 admins: doc_admins,
                 discovery_date: doc_discovery_date,
                 last_update: doc_last_deployment,
                 cause: doc_cause.to_string(),
+                overrides: doc_overrides,
+                stage: doc_stage,
+                stage_entered: doc_stage_entered,
+                notices: doc_notices,
+                snooze: doc_snooze,
+                ignore: doc_ignore,
+                ttl: doc_ttl,
+                labels: doc_labels,
+                heartbeat: doc_heartbeat,
+                signals: doc_signals,
+                spun_down: doc_spun_down,
             };
             namespace_table.push(namespace_document);
         }
@@ -749,7 +1373,7 @@ fn get_db(mongo_client: &mongodb::Client, collection: &str) -> Result<Vec<DBItem
     Ok(namespace_table)
 }
 
-pub fn view_db(mongo_client: &mongodb::Client, collection: &str) -> Result<()> {
+pub fn view_db(mongo_client: &mongodb::Client, config: &Config, collection: &str) -> Result<()> {
     // Query the DB and get back a table of already added namespaces
     let current_table: Vec<DBItem> = get_db(mongo_client, collection)?;
     match collection.as_ref() {
@@ -764,26 +1388,31 @@ pub fn view_db(mongo_client: &mongodb::Client, collection: &str) -> Result<()> {
         }
     }
     let mut db_table = Table::new(); // Create the table
-    db_table.add_row(row!["Namespace", "Admins", "Discovery Date", "Last Update", "Weeks Spent", "Cause"]); // Add a row per time
+    if collection == "ignore" {
+        // Ignored projects don't age, so show why they're ignored instead.
+        db_table.add_row(row!["Namespace", "Admins", "Discovery Date", "Reason", "Requested By", "Expires"]);
+        for row in &current_table {
+            let (reason, requested_by, expires) = match &row.ignore {
+                Some(ignore) => (ignore.reason.as_str(), ignore.requested_by.as_str(), ignore.expires.as_ref().map_or("never", |date| date.as_str())),
+                None => ("unknown", "unknown", "never"),
+            };
+            db_table.add_row(row![
+                row.name,
+                format!("{:?}", row.admins),
+                row.discovery_date,
+                reason,
+                requested_by,
+                expires,
+            ]);
+        }
+        db_table.printstd();
+        return Ok(());
+    }
+    db_table.add_row(row!["Namespace", "Admins", "Discovery Date", "Last Update", "Weeks Spent", "Effective Weeks", "Cause", "Signals", "Stage", "Snoozed Until", "TTL"]); // Add a row per time
     for row in &current_table {
-        // This should be safe. Compare discovery date with last update to see
-        // which is more recent. Copied and pasted from check_expiry_dates().
-        let last_update = DateTime::parse_from_rfc2822(&row.last_update).unwrap();
-        let age = match DateTime::parse_from_rfc2822(&row.discovery_date) {
-            Ok(date) => {
-                let discovery_date = date;
-                let since = match last_update.signed_duration_since(discovery_date) {
-                    d if d > Duration::nanoseconds(0) => Utc::now().signed_duration_since(last_update),
-                    _ => Utc::now().signed_duration_since(discovery_date),
-                };
-                since
-            },
-            _ => {
-                Utc::now().signed_duration_since(last_update)
-            }
-        };
-
-        let weeks_since = Duration::num_weeks(&age);
+        let (raw_age, age) = get_age(row, config);
+        let weeks_since = Duration::num_weeks(&raw_age);
+        let effective_weeks = Duration::num_weeks(&age);
 
         // Avoid panicking due to string manipulation >_>
         let fmt_disc_date = match row.discovery_date.len() {
@@ -802,7 +1431,15 @@ pub fn view_db(mongo_client: &mongodb::Client, collection: &str) -> Result<()> {
             fmt_disc_date,
             fmt_last_update,
             weeks_since,
-            row.cause,
+            effective_weeks,
+            match (&row.heartbeat, row.cause.as_str()) {
+                (Some(heartbeat), "Heartbeat") => format!("Heartbeat ({})", &heartbeat.by),
+                _ => row.cause.to_string(),
+            },
+            fmt_signals(row),
+            row.stage,
+            row.snooze.as_ref().map_or("", |snooze| snooze.until.as_str()),
+            get_ttl(row).map_or(String::new(), |ttl| ttl.to_rfc2822()),
         ]);
     }
     db_table.printstd(); // Print the table to stdout
@@ -813,15 +1450,295 @@ fn add_item_to_db(mongo_client: &mongodb::Client, collection: &str, item: DBItem
     let coll = mongo_client
         .db("SHELFLIFE")
         .collection(&collection);
+    let snooze = match item.snooze {
+        Some(snooze) => Bson::Document(doc!{"granted": snooze.granted,
+                                            "until": snooze.until,
+                                            "reason": snooze.reason,
+                                            "granted_by": snooze.granted_by}),
+        None => Bson::Null,
+    };
+    let ignore = match item.ignore {
+        Some(ignore) => Bson::Document(doc!{"reason": ignore.reason,
+                                            "requested_by": ignore.requested_by,
+                                            "expires": bson::to_bson(&ignore.expires)?}),
+        None => Bson::Null,
+    };
+    let heartbeat = match item.heartbeat {
+        Some(heartbeat) => Bson::Document(doc!{"at": heartbeat.at,
+                                               "by": heartbeat.by}),
+        None => Bson::Null,
+    };
     coll.insert_one(doc!{"name": item.name,
                          "admins": bson::to_bson(&item.admins)?,
                          "discovery_date": item.discovery_date, 
                          "last_update": item.last_update, 
-                         "cause": item.cause}, None)
+                         "cause": item.cause,
+                         "expire_after": bson::to_bson(&item.overrides.expire_after)?,
+                         "exempt_until": bson::to_bson(&item.overrides.exempt_until)?,
+                         "stage_override": bson::to_bson(&item.overrides.stage_override)?,
+                         "ttl_override": bson::to_bson(&item.overrides.ttl)?,
+                         "ttl": bson::to_bson(&item.ttl)?,
+                         "labels": bson::to_bson(&item.labels)?,
+                         "signals": bson::to_bson(&item.signals)?,
+                         "spun_down": spun_down_to_bson(&item.spun_down),
+                         "stage": item.stage,
+                         "stage_entered": item.stage_entered,
+                         "notices": bson::to_bson(&item.notices)?,
+                         "snooze": snooze,
+                         "ignore": ignore,
+                         "heartbeat": heartbeat}, None)
                          .unwrap();
     Ok(())
 }
 
+// Refreshes what the API told us about a namespace, leaving everything
+// ShelfLife itself keeps track of alone.
+fn update_db_item(mongo_client: &mongodb::Client, collection: &str, item: &DBItem) -> Result<()> {
+    let coll = mongo_client
+        .db("SHELFLIFE")
+        .collection(&collection);
+    coll.update_one(doc!{"name": item.name.as_str()},
+                    doc!{"$set": {"admins": bson::to_bson(&item.admins)?,
+                                  "last_update": item.last_update.as_str(),
+                                  "cause": item.cause.as_str(),
+                                  "expire_after": bson::to_bson(&item.overrides.expire_after)?,
+                                  "exempt_until": bson::to_bson(&item.overrides.exempt_until)?,
+                                  "stage_override": bson::to_bson(&item.overrides.stage_override)?,
+                                  "ttl_override": bson::to_bson(&item.overrides.ttl)?,
+                                  "labels": bson::to_bson(&item.labels)?,
+                                  "signals": bson::to_bson(&item.signals)?}},
+                    None)?;
+    Ok(())
+}
+
+// Records when the admins of a namespace were successfully sent a stage's notice.
+fn record_db_item_notice(mongo_client: &mongodb::Client, collection: &str, namespace: &str, stage: &str, delivered: &str) -> Result<()> {
+    let coll = mongo_client
+        .db("SHELFLIFE")
+        .collection(&collection);
+    let mut notice = bson::Document::new();
+    notice.insert(format!("notices.{}", stage), delivered);
+    coll.update_one(doc!{"name": namespace},
+                    doc!{"$set": notice},
+                    None)?;
+    Ok(())
+}
+
+// Records which stage of the policy a namespace is in, and when it got there.
+fn set_db_item_stage(mongo_client: &mongodb::Client, collection: &str, namespace: &str, stage: &str, entered: &str) -> Result<()> {
+    let coll = mongo_client
+        .db("SHELFLIFE")
+        .collection(&collection);
+    coll.update_one(doc!{"name": namespace},
+                    doc!{"$set": {"stage": stage, "stage_entered": entered}},
+                    None)?;
+    Ok(())
+}
+
+fn spun_down_to_bson(spun_down: &[SpunDown]) -> Bson {
+    Bson::Array(spun_down
+        .iter()
+        .map(|deployment| Bson::Document(doc!{"name": deployment.name.as_str(),
+                                              "replicas": deployment.replicas}))
+        .collect())
+}
+
+// Records which deploymentconfigs a spin-down scaled to 0, and how many
+// replicas each one had.
+fn set_db_item_spun_down(mongo_client: &mongodb::Client, collection: &str, namespace: &str, spun_down: &[SpunDown]) -> Result<()> {
+    let coll = mongo_client
+        .db("SHELFLIFE")
+        .collection(&collection);
+    coll.update_one(doc!{"name": namespace},
+                    doc!{"$set": {"spun_down": spun_down_to_bson(spun_down)}},
+                    None)?;
+    Ok(())
+}
+
+// Forgets which stage notices a namespace's admins got, so an old spin-down
+// notice can't count as warning for the next archive. TTL countdown notices
+// stay, since the TTL itself hasn't moved.
+fn clear_db_item_notices(mongo_client: &mongodb::Client, collection: &str, item: &DBItem) -> Result<()> {
+    let mut notices = bson::Document::new();
+    for stage in item.notices.keys().filter(|key| !key.starts_with("TTL ")) {
+        notices.insert(format!("notices.{}", stage), "");
+    }
+    if notices.is_empty() {
+        return Ok(()); // Mongo won't take an empty $unset.
+    }
+    let coll = mongo_client
+        .db("SHELFLIFE")
+        .collection(&collection);
+    coll.update_one(doc!{"name": item.name.as_str()},
+                    doc!{"$unset": notices},
+                    None)?;
+    Ok(())
+}
+
+// Puts a revived namespace back at the start of the policy.
+fn revive_db_item(mongo_client: &mongodb::Client, collection: &str, namespace: &str) -> Result<()> {
+    let coll = mongo_client
+        .db("SHELFLIFE")
+        .collection(&collection);
+    coll.update_one(doc!{"name": namespace},
+                    doc!{"$set": {"stage": "", "stage_entered": "", "spun_down": []}},
+                    None)?;
+    Ok(())
+}
+
+// Gives a tracked namespace an extension. ShelfLife will skip it until `until`,
+// then pick up counting where it left off. Extending a snooze that's still
+// running keeps its start, so the whole stretch comes off the age.
+pub fn snooze_db_item(
+    mongo_client: &mongodb::Client,
+    collection: &str,
+    namespace: &str,
+    until: &str,
+    reason: &str,
+    granted_by: &str,
+) -> Result<()> {
+    let until = parse_date(until)?.to_rfc2822();
+    let coll = mongo_client
+        .db("SHELFLIFE")
+        .collection(collection);
+    let now = Utc::now();
+    let mut granted = now.to_rfc2822();
+    if let Some(existing) = coll.find_one(Some(doc!{"name": namespace}), None)? {
+        if let Ok(snooze) = existing.get_document("snooze") {
+            let start = parse_date(snooze.get_str("granted").unwrap_or_default());
+            let end = parse_date(snooze.get_str("until").unwrap_or_default());
+            if let (Ok(start), Ok(end)) = (start, end) {
+                if end > now {
+                    granted = start.to_rfc2822();
+                }
+            }
+        }
+    }
+    let result = coll.update_one(doc!{"name": namespace},
+                                 doc!{"$set": {"snooze": {"granted": granted.as_str(),
+                                                          "until": until.as_str(),
+                                                          "reason": reason,
+                                                          "granted_by": granted_by}}},
+                                 None)?;
+    if result.matched_count == 0 {
+        return Err(From::from(format!("{} is not in the {} table.", namespace, collection)));
+    }
+    println!("{} has been snoozed until {}.", namespace, until);
+    info!("{} snoozed until {} by {}: {}", namespace, until, granted_by, reason);
+    Ok(())
+}
+
+// Records why a namespace is being ignored, who asked for it, and optionally
+// when ShelfLife should start tracking it again. Unless `overwrite` is set,
+// entries that already have a reason keep it.
+pub fn set_db_item_ignore(
+    mongo_client: &mongodb::Client,
+    namespace: &str,
+    reason: &str,
+    requested_by: &str,
+    expires: Option<&str>,
+    overwrite: bool,
+) -> Result<()> {
+    let expires = match expires {
+        Some(date) => Some(parse_date(date)?.to_rfc2822()),
+        None => None,
+    };
+    let coll = mongo_client
+        .db("SHELFLIFE")
+        .collection("ignore");
+    let filter = match overwrite {
+        true => doc!{"name": namespace},
+        false => doc!{"name": namespace, "ignore": Bson::Null},
+    };
+    let result = coll.update_one(filter,
+                                 doc!{"$set": {"ignore": {"reason": reason,
+                                                          "requested_by": requested_by,
+                                                          "expires": bson::to_bson(&expires)?}}},
+                                 None)?;
+    if result.matched_count == 0 {
+        return Ok(());
+    }
+    info!("{} ignored by {} until {}: {}", namespace, requested_by, expires.as_ref().map_or("forever", |date| date.as_str()), reason);
+    Ok(())
+}
+
+// Takes namespaces whose ignore entries have run out off the ignore list, so
+// the next sync starts tracking them again.
+pub fn release_expired_ignores(mongo_client: &mongodb::Client) -> Result<()> {
+    let ignored: Vec<DBItem> = get_db(mongo_client, "ignore")?;
+    for item in ignored.iter() {
+        let expires = match item.ignore.as_ref().and_then(|ignore| ignore.expires.as_ref()) {
+            Some(expires) => expires,
+            None => continue,
+        };
+        match parse_date(expires) {
+            Ok(date) if date <= Utc::now() => {
+                println!("Stopped ignoring {}. It expired {}.", &item.name, expires);
+                info!("Stopped ignoring {}. It expired {}.", &item.name, expires);
+                remove_db_item(mongo_client, "ignore", &item.name)?;
+            },
+            Ok(_) => {},
+            Err(e) => warn!("Invalid ignore expiry on {}: {}", &item.name, e),
+        }
+    }
+    Ok(())
+}
+
+// Records that somebody vouched for a namespace being in use, and resets its
+// clock. The next sync keeps it unless something newer happens.
+pub fn touch_db_item(mongo_client: &mongodb::Client, collection: &str, namespace: &str, by: &str) -> Result<()> {
+    let now = Utc::now().to_rfc2822();
+    let coll = mongo_client
+        .db("SHELFLIFE")
+        .collection(collection);
+    let mut touched = bson::Document::new();
+    touched.insert("heartbeat", doc!{"at": now.as_str(), "by": by});
+    touched.insert("signals.heartbeat", now.as_str());
+    touched.insert("last_update", now.as_str());
+    touched.insert("cause", "Heartbeat");
+    let result = coll.update_one(doc!{"name": namespace},
+                                 doc!{"$set": touched},
+                                 None)?;
+    if result.matched_count == 0 {
+        return Err(From::from(format!("{} is not in the {} table.", namespace, collection)));
+    }
+    println!("Touched {}.", namespace);
+    info!("{} touched by {}", namespace, by);
+    Ok(())
+}
+
+// Sets the date a namespace gets spun down and archived, however active it is.
+pub fn set_db_item_ttl(mongo_client: &mongodb::Client, collection: &str, namespace: &str, ttl: &str) -> Result<()> {
+    let ttl = parse_date(ttl)?.to_rfc2822();
+    let coll = mongo_client
+        .db("SHELFLIFE")
+        .collection(collection);
+    let item = match coll.find_one(Some(doc!{"name": namespace}), None)? {
+        Some(item) => item,
+        None => return Err(From::from(format!("{} is not in the {} table.", namespace, collection))),
+    };
+
+    // A new TTL gets a new countdown. The old one's notices don't count as
+    // warning for it.
+    let mut update = doc!{"$set": {"ttl": ttl.as_str()}};
+    let mut stale = bson::Document::new();
+    if let Some(&Bson::Document(ref notices)) = item.get("notices") {
+        for key in notices.keys().filter(|key| key.starts_with("TTL ")) {
+            stale.insert(format!("notices.{}", key), "");
+        }
+    }
+    if !stale.is_empty() {
+        update.insert("$unset", stale);
+    }
+    let result = coll.update_one(doc!{"name": namespace}, update, None)?;
+    if result.matched_count == 0 {
+        return Err(From::from(format!("{} is not in the {} table.", namespace, collection)));
+    }
+    println!("{} will be deleted on {}.", namespace, ttl);
+    info!("{} has a TTL of {}", namespace, ttl);
+    Ok(())
+}
+
 pub fn remove_db_item(mongo_client: &mongodb::Client, collection: &str, namespace: &str) -> Result<()> {
     let coll = mongo_client
         .db("SHELFLIFE")
@@ -831,3 +1748,224 @@ pub fn remove_db_item(mongo_client: &mongodb::Client, collection: &str, namespac
     println!("{} has been removed from db.", namespace);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ago(days: i64) -> String {
+        (Utc::now() - Duration::days(days)).to_rfc2822()
+    }
+
+    fn pause(from_days_ago: i64, to_days_ago: i64) -> Window {
+        Window {
+            name: "Break".to_string(),
+            start: (Utc::now() - Duration::days(from_days_ago)).to_rfc3339(),
+            end: (Utc::now() - Duration::days(to_days_ago)).to_rfc3339(),
+        }
+    }
+
+    // Last updated 30 days ago, discovered long before that.
+    fn item() -> DBItem {
+        DBItem {
+            name: "myproject".to_string(),
+            discovery_date: ago(100),
+            last_update: ago(30),
+            ..DBItem::default()
+        }
+    }
+
+    // How many days of pauses came off, give or take the time the test takes.
+    fn paused_days(item: &DBItem, pauses: Vec<Window>) -> i64 {
+        let config = Config { pauses, ..Config::default() };
+        let (raw_age, age) = get_age(item, &config);
+        assert_eq!(raw_age.num_days(), 30);
+        (raw_age - age + Duration::minutes(1)).num_days()
+    }
+
+    #[test]
+    fn get_age_without_pauses_is_the_raw_age() {
+        assert_eq!(paused_days(&item(), Vec::new()), 0);
+    }
+
+    #[test]
+    fn get_age_takes_out_paused_time() {
+        assert_eq!(paused_days(&item(), vec![pause(10, 5)]), 5);
+        assert_eq!(paused_days(&item(), vec![pause(25, 20), pause(10, 5)]), 10);
+    }
+
+    #[test]
+    fn get_age_takes_out_overlapping_pauses_once() {
+        assert_eq!(paused_days(&item(), vec![pause(20, 10), pause(15, 5)]), 15);
+        assert_eq!(paused_days(&item(), vec![pause(20, 5), pause(15, 10)]), 15);
+    }
+
+    #[test]
+    fn get_age_clips_pauses_to_the_time_measured() {
+        // Only the part after the last update, and the part that's happened so far.
+        assert_eq!(paused_days(&item(), vec![pause(40, 25)]), 5);
+        assert_eq!(paused_days(&item(), vec![pause(2, -5)]), 2);
+        assert_eq!(paused_days(&item(), vec![pause(60, 40), pause(-1, -5)]), 0);
+    }
+
+    #[test]
+    fn get_age_takes_out_snoozed_time() {
+        let snooze = |granted: i64, until: i64| Snooze {
+            granted: ago(granted),
+            until: ago(until),
+            reason: "Senior project".to_string(),
+            granted_by: "wilnil".to_string(),
+        };
+        let snoozed = |granted, until| DBItem { snooze: Some(snooze(granted, until)), ..item() };
+        assert_eq!(paused_days(&snoozed(20, 10), Vec::new()), 10);
+        // Overlapping a pause, it still only comes off once.
+        assert_eq!(paused_days(&snoozed(20, 10), vec![pause(15, 5)]), 15);
+        // Old snoozes don't say when they started, so there's nothing to take out.
+        let mut old = snooze(20, 10);
+        old.granted = String::new();
+        assert_eq!(paused_days(&DBItem { snooze: Some(old), ..item() }, Vec::new()), 0);
+    }
+
+    #[test]
+    fn get_age_counts_from_the_discovery_date_if_its_newer() {
+        let item = DBItem { discovery_date: ago(10), ..item() };
+        let (raw_age, age) = get_age(&item, &Config { pauses: vec![pause(20, 5)], ..Config::default() });
+        assert_eq!(raw_age.num_days(), 10);
+        assert_eq!((raw_age - age + Duration::minutes(1)).num_days(), 5);
+    }
+
+    #[test]
+    fn get_age_goes_by_the_stored_signals() {
+        let mut item = item();
+        item.signals.insert("creation".to_string(), ago(200));
+        item.signals.insert("builds".to_string(), ago(20));
+        let mut config = Config::default();
+        assert_eq!(get_age(&item, &config).0.num_days(), 20);
+
+        // Half the weight, twice the age.
+        for source in config.activity.sources.iter_mut().filter(|source| source.name == "builds") {
+            source.weight = 0.5;
+        }
+        assert_eq!(get_age(&item, &config).0.num_days(), 40);
+
+        // The build is too old to count anymore, whatever the last sync thought.
+        for source in config.activity.sources.iter_mut().filter(|source| source.name == "builds") {
+            source.max_age_days = Some(14);
+        }
+        assert_eq!(get_age(&item, &config).0.num_days(), 100);
+    }
+
+    // A tracked project that's gone `days_idle` days without an update.
+    fn tracked(days_idle: i64, stage: &str, notices: &[(&str, i64)]) -> DBItem {
+        DBItem {
+            admins: vec!["alice".to_string()],
+            discovery_date: ago(400),
+            last_update: ago(days_idle),
+            stage: stage.to_string(),
+            stage_entered: if stage == "" { String::new() } else { ago(1) },
+            notices: notices.iter().map(|(key, days)| (key.to_string(), ago(*days))).collect(),
+            ..item()
+        }
+    }
+
+    fn summary(verdict: &Verdict) -> String {
+        let name = |index: &usize| Policy::default().stages[*index].name.clone();
+        match verdict {
+            Verdict::Protected(_) => "protected".to_string(),
+            Verdict::Skipped(_) => "skipped".to_string(),
+            Verdict::Ok => "ok".to_string(),
+            Verdict::Holding(_) => "holding".to_string(),
+            Verdict::Reset => "reset".to_string(),
+            Verdict::Deferred(index, _) => format!("deferred {}", name(index)),
+            Verdict::Blocked(index, _) => format!("blocked {}", name(index)),
+            Verdict::Advance(index) => format!("advance {}", name(index)),
+        }
+    }
+
+    #[test]
+    fn evaluate_walks_projects_through_the_policy() {
+        // Nudge at 84 days, Spin-Down at 112, Archive at 168.
+        let config = Config::default();
+        let cases = vec![
+            // Days idle, stage it's in, notices on record (and how many days ago), what happens.
+            (30, "", vec![], "ok"),
+            (90, "", vec![], "advance Nudge"),
+            (120, "Nudge", vec![("Nudge", 30)], "advance Spin-Down"),
+            // Never more than one stage at a time, however old it's gotten.
+            (200, "", vec![], "advance Nudge"),
+            (200, "Spin-Down", vec![("Spin-Down", 30)], "advance Archive"),
+            (90, "Nudge", vec![("Nudge", 5)], "holding"),
+            (120, "Spin-Down", vec![], "holding"),
+            // Somebody did something. Back to the stage its age calls for.
+            (10, "Spin-Down", vec![("Spin-Down", 30)], "reset"),
+            (90, "Spin-Down", vec![("Spin-Down", 30)], "reset"),
+            // Not enough notice before an archive.
+            (200, "Spin-Down", vec![("Spin-Down", 3)], "blocked Archive"),
+            (200, "Spin-Down", vec![("Nudge", 60)], "blocked Archive"),
+        ];
+        for (days_idle, stage, notices, expected) in cases {
+            let item = tracked(days_idle, stage, &notices);
+            let evaluation = evaluate(&item, &config, false, None);
+            assert_eq!(summary(&evaluation.verdict), expected, "{} days idle in stage \"{}\"", days_idle, stage);
+        }
+    }
+
+    #[test]
+    fn holding_says_when_the_notice_never_went_out() {
+        let config = Config::default();
+        let warned = evaluate(&tracked(90, "Nudge", &[("Nudge", 5)]), &config, false, None);
+        match warned.verdict {
+            Verdict::Holding(reason) => assert_eq!(reason, "Spin-Down comes after 112 days"),
+            other => panic!("expected holding, got {}", summary(&other)),
+        }
+        let unwarned = evaluate(&tracked(90, "Nudge", &[]), &config, false, None);
+        match unwarned.verdict {
+            Verdict::Holding(reason) => assert!(reason.ends_with("its Nudge notice isn't on record"), "{}", reason),
+            other => panic!("expected holding, got {}", summary(&other)),
+        }
+    }
+
+    #[test]
+    fn blackouts_defer_everything_but_nudges() {
+        let config = Config::default();
+        let blackout = pause(1, -1);
+        let nudge = evaluate(&tracked(90, "", &[]), &config, false, Some(&blackout));
+        assert_eq!(summary(&nudge.verdict), "advance Nudge");
+        let spin_down = evaluate(&tracked(120, "Nudge", &[("Nudge", 30)]), &config, false, Some(&blackout));
+        assert_eq!(summary(&spin_down.verdict), "deferred Spin-Down");
+        let archive = evaluate(&tracked(200, "Spin-Down", &[("Spin-Down", 30)]), &config, false, Some(&blackout));
+        assert_eq!(summary(&archive.verdict), "deferred Archive");
+    }
+
+    #[test]
+    fn an_expired_ttl_jumps_to_the_destructive_stages() {
+        let config = Config::default();
+        let mut item = tracked(10, "", &[]);
+        item.ttl = Some(ago(1));
+        let evaluation = evaluate(&item, &config, false, None);
+        assert!(evaluation.ttl_expired);
+        assert_eq!(summary(&evaluation.verdict), "advance Spin-Down");
+
+        // The countdown counts as notice for the archive that follows.
+        let mut item = tracked(10, "Spin-Down", &[("TTL 1d", 20)]);
+        item.ttl = Some(ago(1));
+        assert_eq!(summary(&evaluate(&item, &config, false, None).verdict), "advance Archive");
+
+        // As long as it started far enough ahead of the TTL.
+        let mut item = tracked(10, "Spin-Down", &[("TTL 1d", 2)]);
+        item.ttl = Some(ago(1));
+        assert_eq!(summary(&evaluate(&item, &config, false, None).verdict), "blocked Archive");
+    }
+
+    #[test]
+    fn ttl_countdown_notices_go_out_once_each() {
+        let config = Config::default();
+        let mut item = tracked(10, "", &[]);
+        item.ttl = Some((Utc::now() + Duration::days(5) + Duration::hours(1)).to_rfc2822());
+        let evaluation = evaluate(&item, &config, false, None);
+        assert_eq!(evaluation.ttl_notice, Some(("TTL 7d".to_string(), 5)));
+
+        item.notices.insert("TTL 7d".to_string(), ago(2));
+        assert_eq!(evaluate(&item, &config, false, None).ttl_notice, None);
+    }
+}
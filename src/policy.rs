@@ -0,0 +1,663 @@
+use serde::Deserialize;
+use regex::Regex;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use crate::protocol::DBItem;
+use crate::Result;
+
+// Nothing needs to live longer than this, and anything bigger than it is
+// probably a typo. Keeps chrono from panicking on silly numbers too.
+const MAX_DURATION_DAYS: i64 = 10 * 365;
+
+// ------------------------------
+// What ShelfLife does to a project once it hits a stage.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Nudge,    // Just email the admins.
+    SpinDown, // Scale every deploymentconfig to 0 pods.
+    Archive,  // Export the project, then delete it.
+}
+
+// A single step in a project's life. Once a project has gone `after_days`
+// without an update, ShelfLife takes `action` and sends the admins `subject`
+// and `template`.
+//
+// Templates can use the following placeholders:
+// {namespace}, {last_update}, {age_weeks}, {after_weeks}, {next_stage}, {weeks_until_next}
+#[derive(Debug, Clone, Deserialize)]
+pub struct Stage {
+    pub name: String,
+    pub after_days: i64,
+    pub action: Action,
+    pub subject: String,
+    pub template: String,
+    // Only applies to namespaces the usage check finds idle.
+    #[serde(default)]
+    pub idle_only: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    pub stages: Vec<Stage>,
+    // How long the admins must have known about a spin-down before ShelfLife
+    // will archive their project.
+    pub minimum_notice_days: i64,
+    // Countdown notices sent this many days before a project's TTL.
+    // The TTL templates can use {namespace}, {ttl}, and {days_left}.
+    pub ttl_notice_days: Vec<i64>,
+    pub ttl_subject: String,
+    pub ttl_template: String,
+    // Sent when an admin scales a spun-down project back up. These templates
+    // can use {namespace} and {stage}, the stage it was in.
+    pub revival_subject: String,
+    pub revival_template: String,
+}
+
+impl Default for Policy {
+    // The 12/16/24 week tiers ShelfLife has always used.
+    fn default() -> Self {
+        Policy {
+            stages: vec![
+                Stage {
+                    name: "Nudge".to_string(),
+                    after_days: 12 * 7,
+                    action: Action::Nudge,
+                    subject: "Old OKD project: {namespace}".to_string(),
+                    template: "Hello! You are receiving this message because your OKD project, {namespace}, has gone more than {after_weeks} weeks without an update ({last_update}). Please consider updating with a build, deployment, or asking an RTP to have ShelfLife ignore it. Thanks!.".to_string(),
+                    idle_only: false,
+                },
+                Stage {
+                    name: "Spin-Down".to_string(),
+                    after_days: 16 * 7,
+                    action: Action::SpinDown,
+                    subject: "Your project's resources have been revoked.".to_string(),
+                    template: "Hello! You are receiving this message because your OKD project, {namespace}, has now gone more than {after_weeks} weeks without an update ({last_update}). All applications on the project have now been reduced to 0 pods. If you would like to revive it, do so, and its ShelfLife will reset. Otherwise, it will be deleted in another {weeks_until_next} weeks.".to_string(),
+                    idle_only: false,
+                },
+                Stage {
+                    name: "Archive".to_string(),
+                    after_days: 24 * 7,
+                    action: Action::Archive,
+                    subject: "Hi, I nuked your project :)".to_string(),
+                    template: "Hello! You are receiving this message because your OKD project, {namespace}, has now gone more than {after_weeks} weeks without an update ({last_update}). It has been deleted from OKD. You can find a backup of the project in your homedir at <link>. Thank you for using ShelfLife, try not to let your pods get too moldy next time.".to_string(),
+                    idle_only: false,
+                },
+            ],
+            minimum_notice_days: 14,
+            ttl_notice_days: vec![14, 7, 1],
+            ttl_subject: "Your OKD project {namespace} expires in {days_left} days".to_string(),
+            ttl_template: "Hello! You are receiving this message because your OKD project, {namespace}, is set to expire on {ttl}. That's {days_left} days from now. When it does, ShelfLife will spin it down and then delete it, whether or not it's still being used. Back up anything you want to keep!".to_string(),
+            revival_subject: "Your OKD project {namespace} is back!".to_string(),
+            revival_template: "Hello! You are receiving this message because someone scaled your OKD project, {namespace}, back up after ShelfLife spun it down. It's no longer in the {stage} stage, and its countdown has started over. Thanks for using ShelfLife!".to_string(),
+        }
+    }
+}
+
+impl Policy {
+    // Stages are checked in order, so keep them sorted by threshold no matter
+    // what order they were written in.
+    pub fn sort(&mut self) {
+        self.stages.sort_by_key(|stage| stage.after_days);
+    }
+
+    // Stage names end up as keys in the DB, under `notices.{stage}`. Mongo
+    // won't take dots or a leading $ there, an empty one means "no stage",
+    // and "TTL " is taken by the countdown notices.
+    pub fn validate(&self) -> Result<()> {
+        for stage in self.stages.iter() {
+            if stage.name == "" || stage.name.contains('.') || stage.name.starts_with('$') || stage.name.starts_with("TTL ") {
+                return Err(From::from(format!(
+                    "Error: Invalid stage name \"{}\". Stage names can't be empty, contain a '.', or start with '$' or \"TTL \".",
+                    stage.name,
+                )));
+            }
+            if self.stages.iter().filter(|other| other.name == stage.name).count() > 1 {
+                return Err(From::from(format!("Error: There's more than one stage named \"{}\".", stage.name)));
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the index of the furthest stage a project of this age has reached.
+    pub fn stage_for_age(&self, age: Duration, idle: bool) -> Option<usize> {
+        self.stages
+            .iter()
+            .rposition(|stage| (idle || !stage.idle_only) && age > Duration::days(stage.after_days))
+    }
+
+    // The stage a project moves to after the given one. Idle-only stages are
+    // skipped for projects that are being used.
+    pub fn next_stage(&self, current: Option<usize>, idle: bool) -> Option<usize> {
+        let start = current.map_or(0, |current| current + 1);
+        (start..self.stages.len()).find(|index| idle || !self.stages[*index].idle_only)
+    }
+
+    // Where a project ends up once its TTL runs out: the last destructive
+    // stage that applies whether or not it's idle, or the last stage that
+    // applies at all if the policy never destroys anything.
+    pub fn ttl_stage(&self) -> Option<usize> {
+        self.stages
+            .iter()
+            .rposition(|stage| !stage.idle_only && stage.action != Action::Nudge)
+            .or_else(|| self.stages.iter().rposition(|stage| !stage.idle_only))
+    }
+
+    // Stretches or squishes every stage so that the last one lands at `lifetime`.
+    // Used for the shelflife/expire-after annotation.
+    pub fn with_lifetime(&self, lifetime: Duration) -> Policy {
+        let last = match self.stages.last() {
+            Some(stage) if stage.after_days > 0 => stage.after_days,
+            _ => return self.clone(),
+        };
+        let mut policy = self.clone();
+        for stage in policy.stages.iter_mut() {
+            match stage.after_days.checked_mul(lifetime.num_days()) {
+                Some(days) => stage.after_days = days / last,
+                None => {
+                    warn!("A lifetime of {} days is too long to scale the policy to. Using the default.", lifetime.num_days());
+                    return self.clone();
+                },
+            }
+        }
+        policy
+    }
+
+    // Keeps a project from going past the named stage. Used for the
+    // shelflife/stage-override annotation. "none" means no stage at all.
+    pub fn cap_stage(&self, index: Option<usize>, stage_name: &str) -> Option<usize> {
+        if stage_name.eq_ignore_ascii_case("none") {
+            return None;
+        }
+        match self.stages.iter().position(|stage| stage.name.eq_ignore_ascii_case(stage_name)) {
+            Some(cap) => index.map(|index| index.min(cap)),
+            None => {
+                warn!("Unknown stage in stage-override: {}. Ignoring it.", stage_name);
+                index
+            },
+        }
+    }
+
+    // When the admins of a project were first confirmed to have been warned
+    // before the given stage. Spin-down notices are the warnings that count,
+    // unless the policy never spins anything down. TTL countdown notices only
+    // count when it's the TTL that's archiving the project.
+    pub fn first_warning(&self, index: usize, item: &DBItem, ttl_expired: bool) -> Option<DateTime<Utc>> {
+        let spins_down = self.stages[..index].iter().any(|stage| stage.action == Action::SpinDown);
+        let stage_notices = self.stages[..index]
+            .iter()
+            .filter(|stage| !spins_down || stage.action == Action::SpinDown)
+            .filter_map(|stage| item.notices.get(&stage.name));
+        let ttl_notices = item.notices
+            .iter()
+            .filter(|(key, _)| ttl_expired && key.starts_with("TTL "))
+            .map(|(_, delivered)| delivered);
+        stage_notices
+            .chain(ttl_notices)
+            .filter_map(|delivered| parse_date(delivered).ok())
+            .min()
+    }
+
+    // Picks the countdown notice that's due with this many days left on a TTL.
+    pub fn ttl_countdown(&self, days_left: i64) -> Option<i64> {
+        self.ttl_notice_days
+            .iter()
+            .filter(|days| days_left < **days)
+            .min()
+            .cloned()
+    }
+
+    pub fn render_ttl(&self, text: &str, item: &DBItem, ttl: DateTime<Utc>, days_left: i64) -> String {
+        text.replace("{namespace}", &item.name)
+            .replace("{ttl}", &ttl.to_rfc2822())
+            .replace("{days_left}", &days_left.to_string())
+    }
+
+    pub fn render_revival(&self, text: &str, item: &DBItem) -> String {
+        text.replace("{namespace}", &item.name)
+            .replace("{stage}", &item.stage)
+    }
+
+    // Fills in the placeholders of a stage's subject or template.
+    pub fn render(&self, text: &str, index: usize, item: &DBItem, age: Duration) -> String {
+        let stage = &self.stages[index];
+        let (next_stage, weeks_until_next) = match self.stages.get(index + 1) {
+            Some(next) => (next.name.to_string(), Duration::days(next.after_days - stage.after_days).num_weeks()),
+            None => ("none".to_string(), 0),
+        };
+        text.replace("{namespace}", &item.name)
+            .replace("{last_update}", &item.last_update)
+            .replace("{age_weeks}", &age.num_weeks().to_string())
+            .replace("{after_weeks}", &Duration::days(stage.after_days).num_weeks().to_string())
+            .replace("{next_stage}", &next_stage)
+            .replace("{weeks_until_next}", &weeks_until_next.to_string())
+    }
+}
+
+// Namespaces ShelfLife must never track or cull. A namespace is protected if
+// any rule matches it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Protection {
+    pub names: Vec<String>,
+    pub prefixes: Vec<String>,
+    pub regexes: Vec<String>,
+    // Label selectors, either "key=value" or just "key".
+    pub labels: Vec<String>,
+    // Namespaces nobody is an admin of are usually part of OKD itself. Only
+    // checked when a namespace is discovered, since a project its admins have
+    // all walked away from is exactly what ShelfLife is for.
+    pub zero_admins: bool,
+    // `regexes`, compiled by `compile`.
+    #[serde(skip)]
+    compiled: Vec<Regex>,
+}
+
+impl Default for Protection {
+    fn default() -> Self {
+        Protection {
+            names: vec![
+                "default".to_string(),
+                "management-infra".to_string(),
+                "openshift".to_string(),
+                "kube-system".to_string(),
+                "kube-public".to_string(),
+            ],
+            prefixes: vec!["openshift-".to_string(), "kube-".to_string()],
+            regexes: Vec::new(),
+            labels: Vec::new(),
+            zero_admins: true,
+            compiled: Vec::new(),
+        }
+    }
+}
+
+impl Protection {
+    // Compiles the regexes once, up front. Fails on the first bad one.
+    pub fn compile(&mut self) -> Result<()> {
+        self.compiled = Vec::new();
+        for pattern in self.regexes.iter() {
+            match Regex::new(pattern) {
+                Ok(regex) => self.compiled.push(regex),
+                Err(e) => return Err(From::from(format!("Error: Invalid protection regex {}: {}", pattern, e))),
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the rule that protects this namespace, if any. For namespaces
+    // that aren't tracked yet, use `matches_new` instead.
+    pub fn matches(&self, item: &DBItem) -> Option<String> {
+        if let Some(name) = self.names.iter().find(|name| **name == item.name) {
+            return Some(format!("name {}", name));
+        }
+        if let Some(prefix) = self.prefixes.iter().find(|prefix| item.name.starts_with(prefix.as_str())) {
+            return Some(format!("prefix {}", prefix));
+        }
+        if let Some(regex) = self.compiled.iter().find(|regex| regex.is_match(&item.name)) {
+            return Some(format!("regex {}", regex.as_str()));
+        }
+        for selector in self.labels.iter() {
+            let mut parts = selector.splitn(2, '=');
+            let key = parts.next().unwrap_or_default().trim();
+            let matched = match parts.next() {
+                Some(value) => item.labels.get(key).is_some_and(|label| label == value.trim()),
+                None => item.labels.contains_key(key),
+            };
+            if matched {
+                return Some(format!("label {}", selector));
+            }
+        }
+        None
+    }
+
+    // Same as `matches`, plus the rules that only apply before ShelfLife
+    // starts tracking a namespace.
+    pub fn matches_new(&self, item: &DBItem) -> Option<String> {
+        if let Some(rule) = self.matches(item) {
+            return Some(rule);
+        }
+        if self.zero_admins && item.admins.is_empty() {
+            return Some("0 admins, assuming part of OKD".to_string());
+        }
+        None
+    }
+}
+
+// A named stretch of time, from `start` up to (but not including) `end`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Window {
+    pub name: String,
+    pub start: String,
+    pub end: String,
+}
+
+impl Window {
+    // Makes sure both dates parse, and that the window isn't backwards.
+    pub fn validate(&self) -> Result<()> {
+        match self.bounds() {
+            Ok((start, end)) if start < end => Ok(()),
+            Ok(_) => Err(From::from(format!("Error: The {} window ends before it starts.", &self.name))),
+            Err(e) => Err(From::from(format!("Error: Invalid dates in the {} window: {}", &self.name, e))),
+        }
+    }
+
+    pub fn bounds(&self) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+        Ok((parse_date(&self.start)?, parse_date(&self.end)?))
+    }
+
+    pub fn contains(&self, date: DateTime<Utc>) -> bool {
+        match self.bounds() {
+            Ok((start, end)) => start <= date && date < end,
+            Err(e) => {
+                warn!("Invalid dates in window {}: {}", &self.name, e);
+                false
+            },
+        }
+    }
+}
+
+// Parses durations like "30d", "12w" or "36h". A bare number is days. Has to
+// be more than nothing and no more than MAX_DURATION_DAYS.
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let (number, unit) = match text.chars().last() {
+        Some(unit) if unit.is_ascii_alphabetic() => (&text[..text.len() - 1], unit),
+        _ => (text, 'd'),
+    };
+    let number: i64 = number.trim().parse()?;
+    let hours_per_unit = match unit {
+        'h' => 1,
+        'd' => 24,
+        'w' => 24 * 7,
+        _ => return Err(From::from(format!("Unknown unit in duration: {}", text))),
+    };
+    match number.checked_mul(hours_per_unit) {
+        Some(hours) if hours > 0 && hours <= MAX_DURATION_DAYS * 24 => Ok(Duration::hours(hours)),
+        _ if number <= 0 => Err(From::from(format!("Duration has to be longer than nothing: {}", text))),
+        _ => Err(From::from(format!("Duration is longer than {} days: {}", MAX_DURATION_DAYS, text))),
+    }
+}
+
+// Parses dates like "2021-06-01", or full RFC 3339/2822 timestamps.
+pub fn parse_date(text: &str) -> Result<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    if let Ok(date) = DateTime::parse_from_rfc2822(text) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(text, "%Y-%m-%d")?;
+    Ok(DateTime::<Utc>::from_utc(date.and_hms(0, 0, 0), Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stage(name: &str, after_days: i64, action: Action, idle_only: bool) -> Stage {
+        Stage {
+            name: name.to_string(),
+            after_days,
+            action,
+            subject: String::new(),
+            template: String::new(),
+            idle_only,
+        }
+    }
+
+    // The default tiers, with an idle-only spin-down in front.
+    fn idle_policy() -> Policy {
+        let mut policy = Policy::default();
+        policy.stages.push(stage("Idle", 30, Action::SpinDown, true));
+        policy.sort();
+        policy
+    }
+
+    fn item(name: &str) -> DBItem {
+        DBItem {
+            name: name.to_string(),
+            admins: vec!["wilnil".to_string()],
+            ..DBItem::default()
+        }
+    }
+
+    #[test]
+    fn stage_for_age_finds_the_furthest_stage_reached() {
+        let policy = Policy::default();
+        assert_eq!(policy.stage_for_age(Duration::weeks(10), false), None);
+        assert_eq!(policy.stage_for_age(Duration::weeks(12), false), None); // Has to be past it
+        assert_eq!(policy.stage_for_age(Duration::weeks(13), false), Some(0));
+        assert_eq!(policy.stage_for_age(Duration::weeks(17), false), Some(1));
+        assert_eq!(policy.stage_for_age(Duration::weeks(100), false), Some(2));
+    }
+
+    #[test]
+    fn stage_for_age_skips_idle_only_stages_for_busy_projects() {
+        let policy = idle_policy();
+        assert_eq!(policy.stage_for_age(Duration::days(40), false), None);
+        assert_eq!(policy.stage_for_age(Duration::days(40), true), Some(0));
+        assert_eq!(policy.stage_for_age(Duration::days(100), false), Some(1));
+        assert_eq!(policy.stage_for_age(Duration::days(100), true), Some(1));
+    }
+
+    #[test]
+    fn next_stage_skips_idle_only_stages_for_busy_projects() {
+        let policy = idle_policy();
+        assert_eq!(policy.next_stage(None, true), Some(0));
+        assert_eq!(policy.next_stage(None, false), Some(1));
+        assert_eq!(policy.next_stage(Some(0), false), Some(1));
+        assert_eq!(policy.next_stage(Some(2), false), Some(3));
+        assert_eq!(policy.next_stage(Some(3), true), None);
+    }
+
+    #[test]
+    fn ttl_stage_is_the_last_destructive_stage_for_everybody() {
+        assert_eq!(Policy::default().ttl_stage(), Some(2));
+
+        let mut policy = Policy::default();
+        policy.stages[2].idle_only = true;
+        assert_eq!(policy.ttl_stage(), Some(1));
+
+        let nudges_only = Policy { stages: vec![stage("Nudge", 10, Action::Nudge, false)], ..Policy::default() };
+        assert_eq!(nudges_only.ttl_stage(), Some(0));
+
+        let idle_only = Policy { stages: vec![stage("Idle", 10, Action::SpinDown, true)], ..Policy::default() };
+        assert_eq!(idle_only.ttl_stage(), None);
+    }
+
+    #[test]
+    fn with_lifetime_lands_the_last_stage_on_the_lifetime() {
+        let days: Vec<i64> = Policy::default()
+            .with_lifetime(Duration::weeks(12))
+            .stages.iter().map(|stage| stage.after_days).collect();
+        assert_eq!(days, vec![42, 56, 84]);
+
+        let days: Vec<i64> = Policy::default()
+            .with_lifetime(Duration::weeks(48))
+            .stages.iter().map(|stage| stage.after_days).collect();
+        assert_eq!(days, vec![168, 224, 336]);
+
+        let empty = Policy { stages: Vec::new(), ..Policy::default() };
+        assert!(empty.with_lifetime(Duration::weeks(12)).stages.is_empty());
+    }
+
+    #[test]
+    fn with_lifetime_keeps_the_policy_when_the_math_overflows() {
+        let mut policy = Policy::default();
+        policy.stages[0].after_days = i64::MAX / 2;
+        let days: Vec<i64> = policy.with_lifetime(Duration::weeks(12))
+            .stages.iter().map(|stage| stage.after_days).collect();
+        let unchanged: Vec<i64> = policy.stages.iter().map(|stage| stage.after_days).collect();
+        assert_eq!(days, unchanged);
+    }
+
+    #[test]
+    fn cap_stage_never_goes_past_the_named_stage() {
+        let policy = Policy::default();
+        assert_eq!(policy.cap_stage(Some(2), "Spin-Down"), Some(1));
+        assert_eq!(policy.cap_stage(Some(2), "spin-down"), Some(1));
+        assert_eq!(policy.cap_stage(Some(0), "Archive"), Some(0));
+        assert_eq!(policy.cap_stage(None, "Archive"), None);
+        assert_eq!(policy.cap_stage(Some(2), "none"), None);
+        assert_eq!(policy.cap_stage(Some(2), "Nonsense"), Some(2));
+    }
+
+    #[test]
+    fn first_warning_counts_spin_down_and_ttl_notices() {
+        let policy = Policy::default();
+        let mut item = item("myproject");
+        assert_eq!(policy.first_warning(2, &item, false), None);
+
+        item.notices.insert("Nudge".to_string(), "Mon, 02 Mar 2020 12:00:00 +0000".to_string());
+        item.notices.insert("Spin-Down".to_string(), "Mon, 06 Apr 2020 12:00:00 +0000".to_string());
+        // Before a spin-down, the nudge is all the warning there was.
+        assert_eq!(policy.first_warning(1, &item, false), Some(parse_date("2020-03-02T12:00:00Z").unwrap()));
+        // After one, only the spin-down notice counts.
+        assert_eq!(policy.first_warning(2, &item, false), Some(parse_date("2020-04-06T12:00:00Z").unwrap()));
+
+        item.notices.insert("TTL 14d".to_string(), "Sun, 01 Mar 2020 12:00:00 +0000".to_string());
+        item.notices.insert("TTL 7d".to_string(), "not a date".to_string());
+        assert_eq!(policy.first_warning(2, &item, true), Some(parse_date("2020-03-01T12:00:00Z").unwrap()));
+    }
+
+    #[test]
+    fn first_warning_ignores_ttl_notices_when_age_is_archiving() {
+        let policy = Policy::default();
+        let mut item = item("myproject");
+        item.notices.insert("TTL 14d".to_string(), "Sun, 01 Mar 2020 12:00:00 +0000".to_string());
+        assert_eq!(policy.first_warning(2, &item, false), None);
+
+        item.notices.insert("Spin-Down".to_string(), "Mon, 06 Apr 2020 12:00:00 +0000".to_string());
+        assert_eq!(policy.first_warning(2, &item, false), Some(parse_date("2020-04-06T12:00:00Z").unwrap()));
+    }
+
+    #[test]
+    fn ttl_countdown_picks_the_closest_notice_due() {
+        let policy = Policy::default();
+        assert_eq!(policy.ttl_countdown(30), None);
+        assert_eq!(policy.ttl_countdown(13), Some(14));
+        assert_eq!(policy.ttl_countdown(6), Some(7));
+        assert_eq!(policy.ttl_countdown(0), Some(1));
+    }
+
+    #[test]
+    fn protection_matches_names_prefixes_regexes_and_labels() {
+        let mut protection = Protection {
+            regexes: vec!["^infra-[0-9]+$".to_string()],
+            labels: vec!["shelflife/protected=true".to_string(), "keep".to_string()],
+            ..Protection::default()
+        };
+        protection.compile().unwrap();
+        assert_eq!(protection.matches(&item("openshift")), Some("name openshift".to_string()));
+        assert_eq!(protection.matches(&item("openshift-logging")), Some("prefix openshift-".to_string()));
+        assert_eq!(protection.matches(&item("infra-42")), Some("regex ^infra-[0-9]+$".to_string()));
+        assert_eq!(protection.matches(&item("infra-abc")), None);
+
+        let mut labeled = item("myproject");
+        labeled.labels.insert("shelflife/protected".to_string(), "false".to_string());
+        assert_eq!(protection.matches(&labeled), None);
+        labeled.labels.insert("shelflife/protected".to_string(), "true".to_string());
+        assert_eq!(protection.matches(&labeled), Some("label shelflife/protected=true".to_string()));
+
+        let mut keep = item("myproject");
+        keep.labels.insert("keep".to_string(), "anything".to_string());
+        assert_eq!(protection.matches(&keep), Some("label keep".to_string()));
+    }
+
+    #[test]
+    fn protection_assumes_new_projects_without_admins_are_okd() {
+        let orphan = DBItem { name: "myproject".to_string(), ..DBItem::default() };
+        assert!(Protection::default().matches_new(&orphan).is_some());
+        let protection = Protection { zero_admins: false, ..Protection::default() };
+        assert_eq!(protection.matches_new(&orphan), None);
+    }
+
+    #[test]
+    fn tracked_projects_whose_admins_left_are_not_protected() {
+        let abandoned = DBItem { name: "myproject".to_string(), ..DBItem::default() };
+        assert_eq!(Protection::default().matches(&abandoned), None);
+        let openshift = DBItem { name: "openshift".to_string(), ..DBItem::default() };
+        assert!(Protection::default().matches(&openshift).is_some());
+    }
+
+    #[test]
+    fn bad_protection_regexes_are_refused() {
+        let mut protection = Protection { regexes: vec!["(broken".to_string()], ..Protection::default() };
+        assert!(protection.compile().is_err());
+    }
+
+    #[test]
+    fn stage_names_have_to_work_as_db_keys() {
+        assert!(Policy::default().validate().is_ok());
+        for name in &["", "Spin.Down", "$set", "TTL 7d"] {
+            let mut policy = Policy::default();
+            policy.stages[0].name = name.to_string();
+            assert!(policy.validate().is_err(), "{:?}", name);
+        }
+        let mut policy = Policy::default();
+        policy.stages[1].name = "Nudge".to_string();
+        assert!(policy.validate().is_err());
+    }
+
+    #[test]
+    fn windows_need_real_dates_in_order() {
+        let window = |start: &str, end: &str| Window { name: "Break".to_string(), start: start.to_string(), end: end.to_string() };
+        assert!(window("2020-12-18", "2021-01-04").validate().is_ok());
+        assert!(window("2020-12-18", "whenever").validate().is_err());
+        assert!(window("2021-01-04", "2020-12-18").validate().is_err());
+    }
+
+    #[test]
+    fn window_includes_its_start_but_not_its_end() {
+        let window = Window {
+            name: "Winter break".to_string(),
+            start: "2020-12-18".to_string(),
+            end: "2021-01-04".to_string(),
+        };
+        assert!(!window.contains(parse_date("2020-12-17T23:59:59Z").unwrap()));
+        assert!(window.contains(parse_date("2020-12-18").unwrap()));
+        assert!(window.contains(parse_date("2021-01-03T23:59:59Z").unwrap()));
+        assert!(!window.contains(parse_date("2021-01-04").unwrap()));
+
+        let broken = Window { end: "whenever".to_string(), ..window };
+        assert!(!broken.contains(parse_date("2020-12-25").unwrap()));
+    }
+
+    #[test]
+    fn parse_duration_takes_hours_days_and_weeks() {
+        assert_eq!(parse_duration("36h").unwrap(), Duration::hours(36));
+        assert_eq!(parse_duration("30d").unwrap(), Duration::days(30));
+        assert_eq!(parse_duration(" 12w ").unwrap(), Duration::weeks(12));
+        assert_eq!(parse_duration("45").unwrap(), Duration::days(45));
+        assert!(parse_duration("3y").is_err());
+        assert!(parse_duration("w").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn parse_duration_refuses_nothing_and_negatives() {
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("0w").is_err());
+        assert!(parse_duration("-3d").is_err());
+    }
+
+    #[test]
+    fn parse_duration_refuses_huge_numbers() {
+        assert_eq!(parse_duration("3650d").unwrap(), Duration::days(3650));
+        assert!(parse_duration("3651d").is_err());
+        assert!(parse_duration("99999999999999w").is_err());
+        assert!(parse_duration("9223372036854775807h").is_err());
+        assert!(parse_duration("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn parse_date_takes_dates_and_timestamps() {
+        let midnight = parse_date("2021-06-01").unwrap();
+        assert_eq!(midnight.to_rfc3339(), "2021-06-01T00:00:00+00:00");
+        assert_eq!(parse_date("2021-06-01T02:00:00+02:00").unwrap(), midnight);
+        assert_eq!(parse_date("Tue, 01 Jun 2021 00:00:00 +0000").unwrap(), midnight);
+        assert!(parse_date("06/01/2021").is_err());
+    }
+}
@@ -1,4 +1,46 @@
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+// ------------------------------
+// Every list call comes back with one of these. If there's more than fits in
+// a page, `continue` is what to send back to get the next one.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListMetadata {
+    #[serde(rename = "continue")]
+    pub continue_token: Option<String>,
+}
+
+// The *Response structs below, so the client can stitch pages together.
+pub trait List: DeserializeOwned {
+    fn continue_token(&self) -> Option<&str>;
+    fn extend(&mut self, page: Self);
+}
+
+macro_rules! list {
+    ($($response:ident),*) => {
+        $(
+            impl List for $response {
+                fn continue_token(&self) -> Option<&str> {
+                    // The last page has an empty one, or none at all.
+                    self.metadata.continue_token.as_ref().map(String::as_str).filter(|t| *t != "")
+                }
+
+                fn extend(&mut self, page: Self) {
+                    self.items.extend(page.items);
+                    self.metadata = page.metadata;
+                }
+            }
+        )*
+    };
+}
+
+list!(
+    ProjectResponse, PodsResponse, BuildlistResponse, DeploymentResponse, AppsDeploymentResponse,
+    StatefulSetResponse, CronJobResponse, ImageStreamResponse, EventResponse, RouteResponse,
+    RolebindingsResponse
+);
+// ------------------------------
 
 // ------------------------------
 // Structs for project names
@@ -7,6 +49,8 @@ use serde::Deserialize;
 pub struct ProjectMetadata {
     pub name: String,
     pub creation_timestamp: String,
+    pub labels: Option<HashMap<String, String>>,
+    pub annotations: Option<HashMap<String, String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -18,6 +62,8 @@ pub struct ProjectItem {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProjectResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
     pub items: Vec<ProjectItem>,
 }
 // ------------------------------
@@ -30,15 +76,44 @@ pub struct PodMetadata {
     pub creation_timestamp: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerStateRunning {
+    pub started_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerState {
+    pub running: Option<ContainerStateRunning>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContainerStatus {
+    pub state: Option<ContainerState>,
+    #[serde(default)]
+    pub restart_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PodStatus {
+    pub container_statuses: Option<Vec<ContainerStatus>>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PodItem {
     pub metadata: PodMetadata, 
+    pub status: Option<PodStatus>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PodsResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
     pub items: Vec<PodItem>,
 }
 // ------------------------------
@@ -60,6 +135,8 @@ pub struct BuildItem {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BuildlistResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
     pub items: Vec<BuildItem>,
 }
 // ------------------------------
@@ -95,10 +172,215 @@ pub struct DeploymentItem {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DeploymentResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
     pub items: Vec<DeploymentItem>,
 }
 // ------------------------------
 
+// ------------------------------
+// Structs for Kubernetes Deployments (apps/v1)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppsDeploymentCondition {
+    pub last_update_time: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppsDeploymentStatus {
+    pub conditions: Option<Vec<AppsDeploymentCondition>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppsDeploymentItem {
+    pub metadata: DeploymentMetadata,
+    pub status: Option<AppsDeploymentStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppsDeploymentResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
+    pub items: Vec<AppsDeploymentItem>,
+}
+// ------------------------------
+
+// ------------------------------
+// Structs for StatefulSets
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatefulSetCondition {
+    pub last_transition_time: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatefulSetStatus {
+    pub conditions: Option<Vec<StatefulSetCondition>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatefulSetMetadata {
+    pub name: String,
+    pub creation_timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatefulSetItem {
+    pub metadata: StatefulSetMetadata,
+    pub status: Option<StatefulSetStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatefulSetResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
+    pub items: Vec<StatefulSetItem>,
+}
+// ------------------------------
+
+// ------------------------------
+// Structs for CronJobs
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CronJobStatus {
+    pub last_schedule_time: Option<String>,
+    pub last_successful_time: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CronJobItem {
+    pub metadata: DeploymentMetadata,
+    pub status: Option<CronJobStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CronJobResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
+    pub items: Vec<CronJobItem>,
+}
+// ------------------------------
+
+// ------------------------------
+// Structs for ImageStreams
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagEvent {
+    pub created: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamedTagEventList {
+    pub tag: String,
+    pub items: Option<Vec<TagEvent>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageStreamStatus {
+    pub tags: Option<Vec<NamedTagEventList>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageStreamItem {
+    pub metadata: DeploymentMetadata,
+    pub status: Option<ImageStreamStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageStreamResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
+    pub items: Vec<ImageStreamItem>,
+}
+// ------------------------------
+
+// ------------------------------
+// Structs for Events
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventItem {
+    pub metadata: ProjectMetadata,
+    #[serde(rename = "type")]
+    pub type_: Option<String>, // Normal or Warning
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    pub last_timestamp: Option<String>,
+    pub event_time: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
+    pub items: Vec<EventItem>,
+}
+// ------------------------------
+
+// ------------------------------
+// Structs for Routes
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteSpec {
+    pub host: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteItem {
+    pub spec: RouteSpec,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
+    pub items: Vec<RouteItem>,
+}
+// ------------------------------
+
+// ------------------------------
+// Structs for Prometheus instant queries
+#[derive(Debug, Deserialize)]
+pub struct PrometheusSample {
+    pub value: (f64, String), // [timestamp, "value"]
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrometheusData {
+    pub result: Vec<PrometheusSample>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrometheusResponse {
+    pub status: String,
+    pub data: PrometheusData,
+}
+// ------------------------------
+
+// ------------------------------
+// Structs for Users
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserItem {
+    pub metadata: DeploymentMetadata,
+}
+// ------------------------------
+
 // ------------------------------
 // Structs for Rolebindings
 #[derive(Debug, Deserialize)]
@@ -117,15 +399,73 @@ pub struct RolebindingsItem {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RolebindingsResponse {
+    #[serde(default)]
+    pub metadata: ListMetadata,
     pub items: Vec<RolebindingsItem>,
 }
 // ------------------------------
 
+// Per-namespace policy overrides, read from the project's annotations.
+#[derive(Debug, Default)]
+pub struct Overrides {
+    pub expire_after: Option<String>,   // shelflife/expire-after, e.g. "52w"
+    pub exempt_until: Option<String>,   // shelflife/exempt-until, e.g. "2021-06-01"
+    pub stage_override: Option<String>, // shelflife/stage-override, a stage name or "none"
+    pub ttl: Option<String>,            // shelflife/ttl, e.g. "2021-05-01"
+}
+
+// An extension on a tracked namespace. ShelfLife leaves it alone from `granted`
+// until `until`, and doesn't count that time towards its age.
+#[derive(Debug)]
+pub struct Snooze {
+    pub granted: String, // Empty on snoozes from before we kept track
+    pub until: String,
+    pub reason: String,
+    pub granted_by: String,
+}
+
+// Why a namespace is on the ignore list, who asked, and when to stop ignoring it.
+#[derive(Debug)]
+pub struct Ignore {
+    pub reason: String,
+    pub requested_by: String,
+    pub expires: Option<String>,
+}
+
+// An explicit "we're still using this" from the CLI or the heartbeat endpoint.
+#[derive(Debug)]
+pub struct Heartbeat {
+    pub at: String,
+    pub by: String,
+}
+
+// A deploymentconfig ShelfLife scaled to 0, and how many replicas it had.
+#[derive(Debug, Clone)]
+pub struct SpunDown {
+    pub name: String,
+    pub replicas: i64,
+}
+
 // Struct to represent a DB Object
+#[derive(Default)]
 pub struct DBItem {
     pub name: String,
     pub admins: Vec<String>,
     pub discovery_date: String,
     pub last_update: String,
     pub cause: String,
+    pub overrides: Overrides,
+    pub stage: String,         // The policy stage ShelfLife last moved it to. Empty if none.
+    pub stage_entered: String, // When it got there.
+    pub notices: HashMap<String, String>, // Stage name -> when its notice was delivered.
+    pub snooze: Option<Snooze>,
+    pub ignore: Option<Ignore>,
+    pub ttl: Option<String>, // Set from the CLI. See also overrides.ttl.
+    pub labels: HashMap<String, String>,
+    pub heartbeat: Option<Heartbeat>,
+    // Source name -> the most recent thing it found, as rfc2822. "creation" is
+    // always there; "builds" is the last build, "deploymentconfigs" or
+    // "deployments" the last deploy, and so on. Missing means nothing found.
+    pub signals: HashMap<String, String>,
+    pub spun_down: Vec<SpunDown>, // What the last spin-down scaled to 0.
 }
@@ -0,0 +1,184 @@
+use serde::Deserialize;
+use reqwest::Url;
+use std::env;
+use crate::client::OkdClient;
+use crate::protocol::PrometheusResponse;
+use crate::Result;
+
+// ------------------------------
+// Config for the idle check
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UsageConfig {
+    // Base URL of a Prometheus-compatible API, like
+    // https://thanos-querier.openshift-monitoring.svc:9091. No URL, no idle check.
+    pub prometheus: Option<String>,
+    // How far back to average usage over, in Prometheus duration syntax.
+    pub window: String,
+    // A namespace is idle if it's under both of these over the whole window.
+    pub cpu_cores: f64,
+    pub network_bytes_per_second: f64,
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        UsageConfig {
+            prometheus: None,
+            window: "7d".to_string(),
+            cpu_cores: 0.01,
+            network_bytes_per_second: 100.0,
+        }
+    }
+}
+// ------------------------------
+
+// How much a namespace has been using, on average, over the window.
+#[derive(Debug, Clone, Copy)]
+pub struct Usage {
+    pub cpu_cores: f64,
+    pub network_bytes_per_second: f64,
+}
+
+impl UsageConfig {
+    pub fn enabled(&self) -> bool {
+        self.prometheus.is_some()
+    }
+
+    pub fn is_idle(&self, usage: &Usage) -> bool {
+        usage.cpu_cores < self.cpu_cores && usage.network_bytes_per_second < self.network_bytes_per_second
+    }
+
+    // Asks Prometheus how busy a namespace has been. Namespaces with no
+    // metrics at all (nothing running) count as using nothing.
+    pub fn usage(&self, client: &OkdClient, namespace: &str) -> Result<Usage> {
+        let cpu = format!(
+            "sum(rate(container_cpu_usage_seconds_total{{namespace=\"{}\",container!=\"\"}}[{}]))",
+            namespace, &self.window
+        );
+        let network = format!(
+            "sum(rate({{__name__=~\"container_network_(receive|transmit)_bytes_total\",namespace=\"{}\"}}[{}]))",
+            namespace, &self.window
+        );
+        Ok(Usage {
+            cpu_cores: self.query(client, &cpu)?,
+            network_bytes_per_second: self.query(client, &network)?,
+        })
+    }
+
+    // Runs an instant query that should come back with at most one number.
+    fn query(&self, client: &OkdClient, query: &str) -> Result<f64> {
+        let base = match &self.prometheus {
+            Some(base) => base.trim_end_matches('/'),
+            None => return Err(From::from("No Prometheus configured.")),
+        };
+        // An empty PROMETHEUS_TOKEN (straight out of .env.sample) means unset.
+        let explicit = env::var("PROMETHEUS_TOKEN").ok().filter(|token| !token.is_empty());
+        let token = prometheus_token(base, explicit, client.token());
+        let call = format!("{}/api/v1/query", base);
+        let mut response = client.get_url(&call, &[("query", query)], token.as_deref())?;
+        let json: PrometheusResponse = response.json()?;
+        if json.status != "success" {
+            return Err(From::from(format!("Prometheus query failed: {}", query)));
+        }
+        match json.data.result.first() {
+            Some(sample) => Ok(sample.value.1.parse()?),
+            None => Ok(0.0),
+        }
+    }
+}
+
+// Which token to show Prometheus. PROMETHEUS_TOKEN if there is one. Otherwise
+// our OKD token, but only to a service inside the cluster, where it's OKD's own
+// Prometheus on the other end. Anything else gets no token at all.
+fn prometheus_token(base: &str, explicit: Option<String>, okd_token: String) -> Option<String> {
+    if explicit.is_some() {
+        return explicit;
+    }
+    let in_cluster = Url::parse(base)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.ends_with(".svc") || host.ends_with(".svc.cluster.local")))
+        .unwrap_or(false);
+    if in_cluster && okd_token != "" {
+        Some(okd_token)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::tests::{client, quick, response, serve};
+
+    fn sample(value: &str) -> String {
+        format!(
+            "{{\"status\":\"success\",\"data\":{{\"resultType\":\"vector\",\"result\":[{{\"metric\":{{}},\"value\":[1583841600,\"{}\"]}}]}}}}",
+            value,
+        )
+    }
+
+    fn usage_config(base_url: &str) -> UsageConfig {
+        UsageConfig { prometheus: Some(base_url.to_string()), ..UsageConfig::default() }
+    }
+
+    #[test]
+    fn usage_asks_for_cpu_then_network() {
+        let (base_url, server) = serve(vec![
+            response("200 OK", "", &sample("0.005")),
+            response("200 OK", "", &sample("2048")),
+        ]);
+        let config = usage_config(&base_url);
+        let usage = config.usage(&client(&base_url, quick()), "myproject").unwrap();
+        assert_eq!(usage.cpu_cores, 0.005);
+        assert_eq!(usage.network_bytes_per_second, 2048.0);
+        assert!(!config.is_idle(&usage));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /api/v1/query?query=sum%28rate%28container_cpu_usage_seconds_total"));
+        assert!(requests[1].contains("container_network_"));
+        // 127.0.0.1 isn't in the cluster, so it doesn't get our OKD token.
+        assert!(!requests[0].to_lowercase().contains("authorization"));
+    }
+
+    #[test]
+    fn namespaces_without_metrics_use_nothing() {
+        let empty = "{\"status\":\"success\",\"data\":{\"resultType\":\"vector\",\"result\":[]}}";
+        let (base_url, server) = serve(vec![response("200 OK", "", empty), response("200 OK", "", empty)]);
+        let config = usage_config(&base_url);
+        let usage = config.usage(&client(&base_url, quick()), "myproject").unwrap();
+        assert!(config.is_idle(&usage));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn prometheus_queries_are_retried_like_api_calls() {
+        let (base_url, server) = serve(vec![
+            response("503 Service Unavailable", "", "{}"),
+            response("200 OK", "", &sample("0")),
+            response("200 OK", "", &sample("0")),
+        ]);
+        let config = usage_config(&base_url);
+        assert!(config.usage(&client(&base_url, quick()), "myproject").is_ok());
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn prometheus_errors_are_errors() {
+        let (base_url, server) = serve(vec![response("403 Forbidden", "", "{}")]);
+        let config = usage_config(&base_url);
+        assert!(config.usage(&client(&base_url, quick()), "myproject").is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn our_okd_token_only_goes_to_prometheus_in_the_cluster() {
+        let okd = || "okd-token".to_string();
+        let thanos = "https://thanos-querier.openshift-monitoring.svc:9091";
+        assert_eq!(prometheus_token(thanos, None, okd()), Some("okd-token".to_string()));
+        assert_eq!(prometheus_token("https://prometheus.monitoring.svc.cluster.local", None, okd()), Some("okd-token".to_string()));
+        assert_eq!(prometheus_token("https://prometheus.example.com", None, okd()), None);
+        assert_eq!(prometheus_token("https://prometheus.example.com", Some("prom".to_string()), okd()), Some("prom".to_string()));
+        // Logged in with a client certificate, so there's no token to reuse.
+        assert_eq!(prometheus_token(thanos, None, String::new()), None);
+    }
+}